@@ -0,0 +1,71 @@
+use dathos_engine::{Engine, GameClock, GameState, ManualClock, WindowOptions};
+use rutie::VM;
+use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const FRAME: Duration = Duration::from_micros(16_667);
+
+struct BenchState {
+    clock: ManualClock,
+}
+
+impl GameState for BenchState {
+    fn window_options(&self) -> WindowOptions {
+        WindowOptions {
+            width: 1,
+            height: 1,
+            title: "bench".to_owned(),
+            headless: true,
+        }
+    }
+
+    fn clock(&self) -> Arc<dyn GameClock> { Arc::new(self.clock.clone()) }
+}
+
+/// A headless engine whose clock advances one 60 Hz frame per step, so each
+/// scenario sees the same deltas. The Ruby VM can only be started once per
+/// process, so every scenario of a bench runs in the same engine.
+pub struct Bench {
+    engine: Engine<BenchState>,
+    clock: ManualClock,
+}
+
+impl Bench {
+    /// Starts the engine with `script` as its entry script
+    pub fn start(name: &str, script: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("dathos_bench_{}.rb", name));
+        fs::write(&path, script).expect("failed to write the bench script");
+        let clock = ManualClock::new();
+        let mut engine = Engine::build(
+            path,
+            BenchState {
+                clock: clock.clone(),
+            },
+        );
+        engine.start().expect("failed to start the engine");
+        Bench { engine, clock }
+    }
+
+    /// Runs `setup`, then reports the mean time of `frames` frames after a
+    /// short warm-up. Every object is deleted afterwards.
+    pub fn measure(&mut self, label: &str, setup: &str, frames: u32) {
+        VM::eval(setup).expect("bench setup raised");
+        (0..10).for_each(|_| self.step());
+        let start = Instant::now();
+        (0..frames).for_each(|_| self.step());
+        let per_frame = start.elapsed().as_secs_f64() * 1000.0 / f64::from(frames);
+        println!("{:<40} {:>10.3} ms/frame", label, per_frame);
+
+        VM::eval("Game.stop_all_coroutines; Game.objects.each { |o| Game.delete!(o) }")
+            .expect("bench teardown raised");
+        self.step();
+    }
+
+    pub fn finish(self) { self.engine.shutdown(); }
+
+    fn step(&mut self) {
+        self.clock.advance(FRAME);
+        self.engine.step().expect("frame failed");
+    }
+}
@@ -0,0 +1,48 @@
+//! Resumes thousands of concurrent coroutines every frame, once on the engine's
+//! fibers and once stepping external `Enumerator`s, the way coroutines ran
+//! before they moved to fibers.
+
+mod common;
+
+use common::Bench;
+
+const SCRIPT: &str = r#"
+class FiberRunner < GameObject
+  def on_start!
+    run! do |wait|
+      loop { wait.next_frame }
+    end
+  end
+end
+
+class EnumeratorRunner < GameObject
+  def on_start!
+    @coroutine = Enumerator.new do |yielder|
+      loop { yielder << :next_frame }
+    end
+  end
+
+  def update!
+    @coroutine.next
+  end
+end
+"#;
+
+const FRAMES: u32 = 200;
+
+fn main() {
+    let mut bench = Bench::start("coroutines", SCRIPT);
+    for &count in &[1_000, 5_000] {
+        bench.measure(
+            &format!("fibers, {} coroutines", count),
+            &format!("{}.times {{ Game.create!(FiberRunner.new) }}", count),
+            FRAMES,
+        );
+        bench.measure(
+            &format!("enumerators, {} coroutines", count),
+            &format!("{}.times {{ Game.create!(EnumeratorRunner.new) }}", count),
+            FRAMES,
+        );
+    }
+    bench.finish();
+}
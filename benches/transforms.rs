@@ -0,0 +1,45 @@
+//! Moves thousands of objects by their velocity, once through the native
+//! transform store and once the way scripts did before it: with the position
+//! and velocity kept in Ruby ivars and a new `Vector` allocated every frame.
+
+mod common;
+
+use common::Bench;
+
+const SCRIPT: &str = r#"
+class NativeMover < GameObject
+  def on_start!
+    self.velocity = Vector.new(1, 1)
+  end
+end
+
+class IvarMover < GameObject
+  def on_start!
+    @position = Vector.new(0, 0)
+    @speed = Vector.new(1, 1)
+  end
+
+  def update!
+    @position = @position + @speed * Game.delta_time
+  end
+end
+"#;
+
+const FRAMES: u32 = 200;
+
+fn main() {
+    let mut bench = Bench::start("transforms", SCRIPT);
+    for &count in &[1_000, 5_000] {
+        bench.measure(
+            &format!("native integrate, {} objects", count),
+            &format!("{}.times {{ Game.create!(NativeMover.new) }}", count),
+            FRAMES,
+        );
+        bench.measure(
+            &format!("ruby ivars, {} objects", count),
+            &format!("{}.times {{ Game.create!(IvarMover.new) }}", count),
+            FRAMES,
+        );
+    }
+    bench.finish();
+}
@@ -1,5 +1,7 @@
-use crate::modules::core::CoreModule;
-use crate::modules::draw::{BuildError as DrawBuildError, DrawModule};
+use crate::modules::core::{CoreModule, GameInner, ScriptError, ScriptPhase};
+use crate::modules::draw::{BuildError as DrawBuildError, DrawModule, HeadlessDrawModule};
+use crate::modules::hot_reload::HotReloadModule;
+use crate::modules::physics::{CollisionModule, PhysicsModule};
 use crate::modules::{EngineModule, GameState};
 use rutie::{AnyException, Class, NilClass, Object, VM};
 use std::path::PathBuf;
@@ -8,9 +10,17 @@ use std::path::PathBuf;
 pub enum Error {
     DrawBuild(DrawBuildError),
     InvalidEntryScript(PathBuf),
+    ModuleCycle(Vec<&'static str>),
+    Script(ScriptError),
     Update(UpdateError),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    Continue,
+    Quit,
+}
+
 #[derive(Debug)]
 pub enum UpdateError {
     Interrupt,
@@ -22,6 +32,7 @@ where
     G: GameState,
 {
     entry_script: PathBuf,
+    error_handler: Option<Box<dyn FnMut(&ScriptError)>>,
     game_state: G,
     modules: Vec<Box<dyn EngineModule<G>>>,
 }
@@ -41,6 +52,7 @@ where
     pub fn build(entry_script: PathBuf, game_state: G) -> Self {
         Engine {
             entry_script,
+            error_handler: None,
             game_state,
             modules: Vec::new(),
         }
@@ -54,28 +66,113 @@ where
         self
     }
 
-    pub fn run(mut self) -> Result<(), Error> {
-        VM::init();
-        VM::init_loadpath();
-
-        let draw_module = DrawModule::build(&self.game_state)?;
-        self = self.with_module(CoreModule).with_module(draw_module);
+    /// Registers a callback for exceptions raised by game scripts. Errors are
+    /// also logged through the `log` crate whether or not a handler is set.
+    pub fn with_error_handler<F>(mut self, handler: F) -> Self
+    where
+        F: 'static + FnMut(&ScriptError),
+    {
+        self.error_handler = Some(box handler);
+        self
+    }
 
-        self.initialize()?;
+    /// Runs the game loop until an interrupt or quit request.
+    pub fn run(mut self) -> Result<(), Error> {
+        self.start()?;
         'game: loop {
-            let result = self.update();
-            match result {
-                Ok(()) => {}
+            match self.step() {
+                Ok(Step::Continue) => {}
+                Ok(Step::Quit) => break 'game,
                 Err(UpdateError::Interrupt) => {
-                    println!("Interrupt requested");
+                    info!("Interrupt requested");
                     break 'game;
                 }
                 Err(UpdateError::Unknown(e)) => {
-                    println!("Unknown error: {:?}", e);
+                    let error = ScriptError::new(&e, ScriptPhase::Frame);
+                    error!("{}", error);
+                    self.dispatch_script_error(&error);
                     break 'game;
                 }
             }
         }
+        self.shutdown();
+        Ok(())
+    }
+
+    /// Initializes the Ruby VM and all modules and loads the entry script.
+    /// Must be called once before `step`.
+    pub fn start(&mut self) -> Result<(), Error> {
+        VM::init();
+        VM::init_loadpath();
+
+        self.modules.push(box CoreModule);
+        if self.game_state.physics() {
+            self.modules.push(box PhysicsModule::default());
+        }
+        self.modules.push(box CollisionModule::default());
+        if self.game_state.window_options().headless {
+            self.modules.push(box HeadlessDrawModule);
+        } else {
+            let draw_module = DrawModule::build(&self.game_state)?;
+            self.modules.push(box draw_module);
+        }
+        if self.game_state.hot_reload() {
+            self.modules.push(box HotReloadModule::new(&self.entry_script));
+        }
+        self.sort_modules()?;
+
+        self.initialize()
+    }
+
+    /// Advances the game by a single frame.
+    pub fn step(&mut self) -> Result<Step, UpdateError> {
+        let result = self.update();
+        self.handle_script_errors();
+        result?;
+        Ok(if CoreModule::quit_requested() {
+            Step::Quit
+        } else {
+            Step::Continue
+        })
+    }
+
+    /// Runs every module's `shutdown` hook and tears the modules down,
+    /// closing the window if there is one.
+    pub fn shutdown(mut self) {
+        let modules = &mut self.modules;
+        let game_state = &mut self.game_state;
+        let result = VM::protect(|| {
+            modules.iter_mut().for_each(|m| m.shutdown(game_state));
+            NilClass::new().to_any_object()
+        });
+        if result.is_err() {
+            let error = match VM::error_pop() {
+                Ok(e) => ScriptError::new(&e, ScriptPhase::Shutdown),
+                Err(_) => ScriptError::unknown(ScriptPhase::Shutdown),
+            };
+            error!("{}", error);
+            self.dispatch_script_error(&error);
+        }
+        self.handle_script_errors();
+    }
+
+    /// Gives read access to the game state between frames.
+    pub fn inspect_game<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&GameInner) -> R,
+    {
+        CoreModule::inspect(f)
+    }
+
+    /// Reorders the modules to satisfy their `run_after` and `run_before`
+    /// constraints, otherwise keeping the order they were added in.
+    fn sort_modules(&mut self) -> Result<(), Error> {
+        let order = module_order(&self.modules)?;
+        let mut modules = self.modules.drain(..).map(Some).collect::<Vec<_>>();
+        self.modules = order
+            .into_iter()
+            .filter_map(|i| modules[i].take())
+            .collect();
         Ok(())
     }
 
@@ -83,29 +180,52 @@ where
         let modules = &mut self.modules;
         let game_state = &mut self.game_state;
         modules.iter_mut().for_each(|m| m.init(game_state));
+        let entry_script = self
+            .entry_script
+            .to_str()
+            .ok_or_else(|| Error::InvalidEntryScript(self.entry_script.clone()))?;
         let result = VM::protect(|| {
-            VM::require(
-                &self
-                    .entry_script
-                    .to_str()
-                    .ok_or(Error::InvalidEntryScript(self.entry_script.clone()))
-                    .unwrap(),
-            );
+            VM::require(entry_script);
             NilClass::new().to_any_object()
         });
-        if let Err(_) = result {
-            println!("Error: {:?}", VM::error_info());
+        if result.is_err() {
+            let error = match VM::error_pop() {
+                Ok(e) => ScriptError::new(&e, ScriptPhase::Load),
+                Err(_) => ScriptError::unknown(ScriptPhase::Load),
+            };
+            error!("{}", error);
+            self.dispatch_script_error(&error);
+            return Err(Error::Script(error));
         }
+        self.handle_script_errors();
         Ok(())
     }
 
+    fn handle_script_errors(&mut self) {
+        CoreModule::take_script_errors()
+            .iter()
+            .for_each(|e| self.dispatch_script_error(e));
+    }
+
+    fn dispatch_script_error(&mut self, error: &ScriptError) {
+        if let Some(handler) = &mut self.error_handler {
+            handler(error);
+        }
+    }
+
     fn update(&mut self) -> Result<(), UpdateError> {
         let modules = &mut self.modules;
         let game_state = &mut self.game_state;
         let result = VM::protect(|| {
             modules.iter_mut().for_each(|m| m.pre_update(game_state));
+            for _ in 0..CoreModule::fixed_steps() {
+                modules.iter_mut().for_each(|m| m.fixed_update(game_state));
+            }
             modules.iter_mut().for_each(|m| m.update(game_state));
             modules.iter_mut().for_each(|m| m.post_update(game_state));
+            modules.iter_mut().for_each(|m| m.pre_render(game_state));
+            modules.iter_mut().for_each(|m| m.render(game_state));
+            modules.iter_mut().for_each(|m| m.post_render(game_state));
             NilClass::new().to_any_object()
         });
         match result {
@@ -126,3 +246,44 @@ where
         }
     }
 }
+
+fn module_order<G>(modules: &[Box<dyn EngineModule<G>>]) -> Result<Vec<usize>, Error>
+where
+    G: GameState,
+{
+    let names = modules.iter().map(|m| m.name()).collect::<Vec<_>>();
+    let indices_of = |name: &str| {
+        (0..names.len())
+            .filter(|&i| names[i] == name)
+            .collect::<Vec<_>>()
+    };
+
+    let mut runs_after = vec![Vec::new(); modules.len()];
+    for (i, module) in modules.iter().enumerate() {
+        for name in module.run_after() {
+            runs_after[i].extend(indices_of(name));
+        }
+        for name in module.run_before() {
+            indices_of(name).into_iter().for_each(|j| runs_after[j].push(i));
+        }
+    }
+
+    let mut placed = vec![false; modules.len()];
+    let mut order = Vec::with_capacity(modules.len());
+    while order.len() < modules.len() {
+        let next = (0..modules.len())
+            .find(|&i| !placed[i] && runs_after[i].iter().all(|&j| placed[j]));
+        match next {
+            Some(i) => {
+                placed[i] = true;
+                order.push(i);
+            }
+            None => {
+                return Err(Error::ModuleCycle(
+                    (0..names.len()).filter(|&i| !placed[i]).map(|i| names[i]).collect(),
+                ))
+            }
+        }
+    }
+    Ok(order)
+}
@@ -13,10 +13,20 @@
 #[macro_use]
 extern crate lazy_static;
 #[macro_use]
+extern crate log;
+#[macro_use]
 extern crate rutie;
 
-pub use crate::engine::{Engine, Error as EngineError};
-pub use crate::modules::{EngineModule, GameState, WindowOptions};
+pub use crate::engine::{Engine, Error as EngineError, Step, UpdateError};
+pub use crate::modules::core::{
+    register_component, CoreModule, Delivery, GameClock, GameInner, ManualClock, NativeComponent,
+    ScriptError, ScriptPhase, Time,
+};
+pub use crate::modules::physics::{CollisionModule, PhysicsModule};
+pub use crate::modules::{
+    EngineModule, FixedTimestep, GameState, WindowOptions, COLLISION_MODULE, CORE_MODULE,
+    DRAW_MODULE, PHYSICS_MODULE,
+};
 
 mod engine;
 pub mod ext;
@@ -1,18 +1,73 @@
+use self::core::GameClock;
+use quanta::Clock;
+use std::any::type_name;
+use std::sync::Arc;
+
 pub mod core;
 pub mod draw;
+pub mod hot_reload;
+pub mod physics;
+
+/// Name of the built-in module that finds collisions and calls their hooks
+pub const COLLISION_MODULE: &str = "collision";
+/// Name of the built-in module that runs game objects and coroutines
+pub const CORE_MODULE: &str = "core";
+/// Name of the built-in module that renders the `Draw` commands
+pub const DRAW_MODULE: &str = "draw";
+/// Name of the built-in module that moves bodies
+pub const PHYSICS_MODULE: &str = "physics";
 
+/// Each frame, every phase runs on all modules before the next phase begins:
+/// `pre_update`, `fixed_update` (zero or more times), `update`, `post_update`,
+/// `pre_render`, `render` and `post_render`. Within a phase, modules run in the
+/// order they were added unless `run_after` or `run_before` say otherwise.
 pub trait EngineModule<G>
 where
     G: GameState,
 {
+    /// Identifies the module in other modules' ordering constraints
+    fn name(&self) -> &'static str { type_name::<Self>() }
+    /// Names of modules whose hooks must run before this module's
+    fn run_after(&self) -> Vec<&'static str> { Vec::new() }
+    /// Names of modules whose hooks must run after this module's
+    fn run_before(&self) -> Vec<&'static str> { Vec::new() }
+
     fn init(&mut self, _: &mut G) {}
     fn pre_update(&mut self, _: &mut G) {}
+    fn fixed_update(&mut self, _: &mut G) {}
     fn update(&mut self, _: &mut G) {}
     fn post_update(&mut self, _: &mut G) {}
+    fn pre_render(&mut self, _: &mut G) {}
+    fn render(&mut self, _: &mut G) {}
+    fn post_render(&mut self, _: &mut G) {}
+    fn shutdown(&mut self, _: &mut G) {}
 }
 
 pub trait GameState {
     fn window_options(&self) -> WindowOptions;
+
+    /// Runs `fixed_update` at a constant rate instead of once per frame.
+    fn fixed_timestep(&self) -> Option<FixedTimestep> { None }
+
+    /// Reloads game scripts while the game is running when they change on
+    /// disk. Meant for development only.
+    fn hot_reload(&self) -> bool { false }
+
+    /// Adds the physics module, which moves objects with a `Body`.
+    /// Collisions between colliders are found either way.
+    fn physics(&self) -> bool { false }
+
+    /// The time source for the game. Defaults to the system clock.
+    fn clock(&self) -> Arc<dyn GameClock> { Arc::new(Clock::new()) }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FixedTimestep {
+    /// Simulation ticks per second, at least 1
+    pub rate: u32,
+    /// Maximum number of ticks run in a single frame before the backlog is
+    /// dropped, at least 1
+    pub max_steps: u32,
 }
 
 #[derive(Clone)]
@@ -20,6 +75,9 @@ pub struct WindowOptions {
     pub width: u32,
     pub height: u32,
     pub title: String,
+    /// Runs without opening a window. Draw commands are accepted but never
+    /// rendered.
+    pub headless: bool,
 }
 
 impl GameState for WindowOptions {
@@ -1,19 +1,41 @@
+pub use self::clock::{GameClock, ManualClock};
 pub use self::color::ColorData;
+pub use self::component::{register_component, NativeComponent};
 pub use self::coroutines::{Coroutine, Coroutines};
+pub use self::events::Delivery;
+use self::component::{Component, Removed};
 use self::game::GAME_WRAPPER;
+pub use self::game::{GameInner, Time};
 pub use self::game_object::GameObject;
 pub use self::input::{Input, INPUT_WRAPPER};
+pub use self::script_error::{ScriptError, ScriptPhase};
+pub use self::transform::Transform;
+pub use self::transform_store::TransformStore;
 pub use self::vector::VectorData;
-use super::{EngineModule, GameState};
-use rutie::{Module, Object, VM};
+use super::{EngineModule, GameState, CORE_MODULE};
+use rutie::{AnyObject, Boolean, Module, Object, VM};
+use std::cell::RefCell;
+use std::rc::Rc;
 
+mod clock;
 mod color;
+mod component;
 mod coroutines;
+mod easing;
+mod events;
 mod game;
 mod game_object;
+mod hierarchy;
 mod input;
+mod object_index;
+mod pool;
 mod rotation;
+mod scene;
+mod script_error;
+mod timer;
 mod transform;
+mod transform_store;
+mod tween;
 mod vector;
 mod wait;
 
@@ -22,18 +44,170 @@ const GAME_UTILS_MODULE: &str = include_str!("./core/game_utils.rb");
 pub struct CoreModule;
 
 impl CoreModule {
+    /// Number of fixed updates to run this frame, as measured in `pre_update`
+    pub fn fixed_steps() -> u32 {
+        Module::from_existing("Game")
+            .instance_variable_get("@inner")
+            .get_data(&*GAME_WRAPPER)
+            .time
+            .fixed_steps
+    }
+
+    /// Whether a script has called `Game.quit!`
+    pub fn quit_requested() -> bool {
+        Self::inspect(|game| game.quit_requested)
+    }
+
+    /// Asks the game to quit, as when the window is closed. The `Game.on_quit`
+    /// callback may veto the request by returning `false`.
+    pub fn request_quit() {
+        let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+        let on_quit = inner.get_data(&*GAME_WRAPPER).on_quit.clone();
+        let allowed = match on_quit {
+            Some(callback) => match callback.protect_send("call", &[]) {
+                Ok(result) => result
+                    .try_convert_to::<Boolean>()
+                    .map(|b| b.to_bool())
+                    .unwrap_or(true),
+                Err(e) => {
+                    ScriptError::new(&e, ScriptPhase::Quit).report();
+                    true
+                }
+            },
+            None => true,
+        };
+        if allowed {
+            inner.get_data_mut(&*GAME_WRAPPER).quit_requested = true;
+        }
+    }
+
+    /// Takes the script errors reported since the last call
+    pub fn take_script_errors() -> Vec<ScriptError> {
+        let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+        inner
+            .get_data_mut(&*GAME_WRAPPER)
+            .script_errors
+            .drain(..)
+            .collect()
+    }
+
+    /// Emits an event to scripted and native subscribers alike
+    pub fn emit(event: &str, payload: AnyObject, delivery: Delivery) {
+        self::events::emit(event, payload, delivery);
+    }
+
+    /// Subscribes a Rust handler to an event, returning an ID for
+    /// `unsubscribe`
+    pub fn subscribe<F>(event: &str, handler: F) -> u64
+    where
+        F: 'static + FnMut(&AnyObject),
+    {
+        let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+        inner
+            .get_data_mut(&*GAME_WRAPPER)
+            .events
+            .subscribe_native(event, Rc::new(RefCell::new(handler)))
+    }
+
+    pub fn unsubscribe(id: u64) -> bool {
+        let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+        inner.get_data_mut(&*GAME_WRAPPER).events.unsubscribe(id)
+    }
+
+    pub fn set_reloading(reloading: bool) {
+        let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+        inner.get_data_mut(&*GAME_WRAPPER).reloading = reloading;
+    }
+
+    pub fn inspect<F, R>(f: F) -> R
+    where
+        F: FnOnce(&GameInner) -> R,
+    {
+        let inner = Module::from_existing("Game").instance_variable_get("@inner");
+        f(inner.get_data(&*GAME_WRAPPER))
+    }
+
+    /// Gives modules that move or query game objects mutable access to the
+    /// game state. Must not be called from within another `inspect_mut`.
+    pub fn inspect_mut<F, R>(f: F) -> R
+    where
+        F: FnOnce(&mut GameInner) -> R,
+    {
+        let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+        f(inner.get_data_mut(&*GAME_WRAPPER))
+    }
+
+    /// Deferred events are delivered in the order emitted. Events emitted
+    /// during delivery wait for the next frame.
+    fn deliver_events(&mut self) {
+        let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+        let queued = inner.get_data_mut(&*GAME_WRAPPER).events.take_queued();
+        queued
+            .iter()
+            .for_each(|(event, payload)| self::events::dispatch(event, payload));
+    }
+
+    fn handle_scene_changes(&mut self) {
+        let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+        let game = inner.get_data_mut(&*GAME_WRAPPER);
+        let changes = game.pending_scene_changes.drain(..).collect::<Vec<_>>();
+        changes
+            .into_iter()
+            .for_each(|change| game.apply_scene_change(change));
+    }
+
     fn handle_pending_deletes(&mut self) {
         let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
         let game = inner.get_data_mut(&*GAME_WRAPPER);
         let mut deletes = Vec::new();
         deletes.append(&mut game.pending_deletes);
+        let descendants = deletes
+            .iter()
+            .filter_map(GameObject::id)
+            .flat_map(|id| game.hierarchy.descendants(id))
+            .collect::<Vec<_>>();
+        deletes.extend(descendants);
+        game.pending_creates.retain(|o| !deletes.contains(o));
+
+        let (despawns, deletes) = deletes
+            .into_iter()
+            .partition::<Vec<_>, _>(|o| o.pool().is_some());
+        game.pending_spawns.retain(|o| !despawns.contains(o));
+        game.game_objects.retain(|o| !despawns.contains(o));
+        for game_object in despawns {
+            game_object.id().into_iter().for_each(|id| {
+                game.index.hide(id);
+                game.hierarchy.remove(id);
+                game.events.unsubscribe_owner(id, None);
+            });
+            game_object.pool().unwrap().release(game_object);
+        }
+
+        let index = &mut game.index;
+        let hierarchy = &mut game.hierarchy;
+        let components = &mut game.components;
+        let events = &mut game.events;
         game.game_objects.retain(|o| {
             let should_remove = deletes.contains(o);
             if should_remove {
                 o.on_delete();
+                o.stop_coroutines();
+                o.id().into_iter().for_each(|id| {
+                    index.remove(id);
+                    hierarchy.remove(id);
+                    events.unsubscribe_owner(id, None);
+                    components
+                        .remove_owner(id)
+                        .into_iter()
+                        .for_each(Removed::on_delete);
+                });
             }
             !should_remove
         });
+        deletes
+            .iter()
+            .filter_map(GameObject::id)
+            .for_each(|id| game.transforms.remove(id));
     }
 
     fn handle_pending_creates(&mut self) {
@@ -47,53 +221,130 @@ impl CoreModule {
                 .try_convert_to::<Coroutines>()
                 .unwrap_or_else(|_| Coroutines::new());
             game_object.instance_variable_set("@coroutines", coroutines);
+            let id = game_object.ensure_id();
+            game.index.insert(id, game_object.clone());
             game_object.on_start();
         }
         game.game_objects.append(&mut creates);
     }
+
+    /// Pooled objects skip most of the create and delete bookkeeping, as
+    /// they keep their ID, components and transform between uses.
+    fn handle_pending_spawns(&mut self) {
+        let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+        let game = inner.get_data_mut(&*GAME_WRAPPER);
+        let mut spawns = Vec::new();
+        spawns.append(&mut game.pending_spawns);
+        for game_object in &spawns {
+            game_object
+                .id()
+                .into_iter()
+                .for_each(|id| game.index.insert(id, game_object.clone()));
+            if game_object.is_reused() {
+                game_object.on_reuse();
+            } else {
+                game_object.on_start();
+            }
+        }
+        game.game_objects.append(&mut spawns);
+    }
 }
 
 impl<G> EngineModule<G> for CoreModule
 where
     G: GameState,
 {
-    fn init(&mut self, _: &mut G) {
+    fn name(&self) -> &'static str { CORE_MODULE }
+
+    fn init(&mut self, game_state: &mut G) {
         let _ = VM::eval(GAME_UTILS_MODULE);
 
         self::color::add_color_class();
         self::rotation::add_rotation_module();
         self::coroutines::add_coroutines_class();
+        self::timer::add_timer_class();
+        self::tween::add_tween_class();
         self::vector::add_vector_class();
         self::transform::add_transform_class();
         self::wait::add_classes();
         self::game_object::add_game_object_class();
-        self::game::add_game_module();
+        self::pool::add_pool_class();
+        self::component::add_component_class();
+        self::scene::add_scene_class();
+        self::game::add_game_module(game_state.clock(), game_state.fixed_timestep());
         self::input::add_input_module();
     }
 
     fn pre_update(&mut self, _: &mut G) {
         let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
-        let mut game = inner.get_data_mut(&*GAME_WRAPPER);
+        let game = inner.get_data_mut(&*GAME_WRAPPER);
         let now = game.time.clock.now();
-        game.time.frame += 1;
-        game.time.delta = now - game.time.now;
-        game.time.now = now;
+        game.time.advance(now);
     }
 
-    fn update(&mut self, _: &mut G) {
+    fn fixed_update(&mut self, _: &mut G) {
         let inner = Module::from_existing("Game").instance_variable_get("@inner");
         let game = inner.get_data(&*GAME_WRAPPER);
-        game.game_objects.iter().for_each(GameObject::update);
-        game.game_objects
+        game.update_sequence()
             .iter()
-            .for_each(GameObject::execute_coroutines);
+            .for_each(GameObject::fixed_update);
+    }
+
+    /// Game objects are updated in their update sequence, each followed by its
+    /// components in the order they were added. Native components are updated
+    /// next, then every object is moved by its velocity and its coroutines
+    /// run, followed by the game's own coroutines. `late_update!` comes last,
+    /// in the object update sequence.
+    fn update(&mut self, _: &mut G) {
+        let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+        let game = inner.get_data_mut(&*GAME_WRAPPER);
+        let index = &game.index;
+        game.components
+            .take_pending_starts(|id| index.get(id).is_some())
+            .iter()
+            .for_each(Component::on_start);
+
+        let running = game.update_sequence();
+        running.iter().for_each(|o| {
+            o.update();
+            o.id().into_iter().for_each(|id| {
+                game.components
+                    .scripted_of(id)
+                    .iter()
+                    .for_each(Component::update)
+            });
+        });
+
+        let deltas = running
+            .iter()
+            .filter_map(|o| o.id().map(|id| (id, game.delta_for(o))))
+            .collect();
+        game.components.update_native(&deltas);
+        game.transforms.integrate(&deltas);
+
+        running.iter().for_each(GameObject::execute_coroutines);
+        if !game.time.paused {
+            let delta = game.time.delta;
+            game.coroutines.execute(delta, None);
+        }
+        running.iter().for_each(GameObject::late_update);
         game.game_objects
             .iter()
             .for_each(GameObject::tidy_coroutines);
+        game.coroutines.tidy();
     }
 
     fn post_update(&mut self, _: &mut G) {
+        self.deliver_events();
+        self.handle_scene_changes();
         self.handle_pending_deletes();
         self.handle_pending_creates();
+        self.handle_pending_spawns();
+    }
+
+    fn shutdown(&mut self, _: &mut G) {
+        let inner = Module::from_existing("Game").instance_variable_get("@inner");
+        let game = inner.get_data(&*GAME_WRAPPER);
+        game.game_objects.iter().for_each(GameObject::on_delete);
     }
 }
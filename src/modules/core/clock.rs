@@ -0,0 +1,39 @@
+use quanta::{Clock, Instant, Mock};
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Source of the current time for `Game.time`, `Game.delta_time` and
+/// coroutine scheduling.
+pub trait GameClock: Debug {
+    fn now(&self) -> Instant;
+}
+
+/// A clock that only moves when the host advances it, for deterministic tests
+/// of timing-dependent scripts.
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    clock: Clock,
+    mock: Arc<Mock>,
+}
+
+impl GameClock for Clock {
+    fn now(&self) -> Instant { Clock::now(self) }
+}
+
+impl GameClock for ManualClock {
+    fn now(&self) -> Instant { self.clock.now() }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        let (clock, mock) = Clock::mock();
+        ManualClock { clock, mock }
+    }
+
+    pub fn advance(&self, duration: Duration) { self.mock.increment(duration); }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self { ManualClock::new() }
+}
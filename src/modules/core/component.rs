@@ -0,0 +1,346 @@
+use super::game::GAME_WRAPPER;
+use super::game_object::GameObject;
+use super::script_error::{ScriptError, ScriptPhase};
+use rutie::{AnyObject, Class, Hash, Module, NilClass, Object, VerifiedObject, GC};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
+
+wrappable_struct!(
+    NativeHandleInner,
+    NativeHandleWrapper,
+    NATIVE_HANDLE_WRAPPER,
+    mark(data) {
+        GC::mark(&data.owner);
+    }
+);
+
+class!(Component);
+
+/// Behaviour written in Rust that is attached to game objects from Ruby with
+/// `add_component` and updated each frame without calling into Ruby.
+pub trait NativeComponent {
+    fn on_start(&mut self, _: &GameObject) {}
+    fn update(&mut self, owner: &GameObject, delta: Duration);
+    fn on_delete(&mut self, _: &GameObject) {}
+}
+
+type NativeComponentFactory = Box<dyn Fn(Option<Hash>) -> Box<dyn NativeComponent>>;
+
+struct NativeComponentType {
+    class: Class,
+    factory: NativeComponentFactory,
+}
+
+thread_local! {
+    static NATIVE_COMPONENT_TYPES: RefCell<Vec<NativeComponentType>> = RefCell::new(Vec::new());
+}
+
+pub struct NativeHandleInner {
+    owner: GameObject,
+}
+
+pub struct NativeEntry {
+    owner: GameObject,
+    handle: AnyObject,
+    state: Rc<NativeState>,
+}
+
+/// Shared with the snapshot `update_native` runs from
+struct NativeState {
+    component: RefCell<Box<dyn NativeComponent>>,
+    started: Cell<bool>,
+    /// Set when the component is removed from within its own hook, which then
+    /// calls `on_delete` once it returns
+    deleted: Cell<bool>,
+}
+
+/// Components attached to game objects, keyed by the owner's ID. Scripted
+/// components run in the order they were added to their owner, and native
+/// components in the order they were added overall.
+#[derive(Default)]
+pub struct ComponentStore {
+    scripted: HashMap<u64, Vec<Component>>,
+    pending_starts: Vec<Component>,
+    native: Vec<NativeEntry>,
+}
+
+/// Defines a Ruby class with the given name for a native component type. Game
+/// objects create an instance with `add_component(Name, options)`, which are
+/// passed to the factory.
+pub fn register_component<F>(name: &str, factory: F)
+where
+    F: 'static + Fn(Option<Hash>) -> Box<dyn NativeComponent>,
+{
+    let mut class = Class::new(name, None);
+    class.def("game_object", native_game_object);
+    NATIVE_COMPONENT_TYPES.with(|types| {
+        types.borrow_mut().push(NativeComponentType {
+            class,
+            factory: Box::new(factory),
+        })
+    });
+}
+
+impl Component {
+    pub fn owner(&self) -> Option<GameObject> {
+        self.instance_variable_get("@game_object")
+            .try_convert_to::<GameObject>()
+            .ok()
+    }
+
+    pub fn on_start(&self) { self.send_hook("on_start!", ScriptPhase::Start); }
+
+    pub fn update(&self) { self.send_hook("update!", ScriptPhase::Update); }
+
+    pub fn on_delete(&self) { self.send_hook("on_delete!", ScriptPhase::Delete); }
+
+    fn send_hook(&self, hook: &str, phase: ScriptPhase) {
+        let result = self.protect_send(hook, &[]);
+        if let Err(e) = result {
+            ScriptError::new(&e, phase).with_object(self).report();
+        }
+    }
+}
+
+impl Clone for Component {
+    fn clone(&self) -> Self {
+        Component {
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl ComponentStore {
+    /// Attaches either a `Component` instance or a registered native component
+    /// class, returning the Ruby object that represents the component.
+    pub fn add(
+        &mut self,
+        owner: &GameObject,
+        id: u64,
+        component: AnyObject,
+        options: Option<Hash>,
+    ) -> Option<AnyObject> {
+        if let Ok(mut component) = component.try_convert_to::<Component>() {
+            component.instance_variable_set("@game_object", owner.clone());
+            self.scripted.entry(id).or_default().push(component.clone());
+            self.pending_starts.push(component.clone());
+            return Some(component.to_any_object());
+        }
+
+        let class = component.try_convert_to::<Class>().ok()?;
+        let native = NATIVE_COMPONENT_TYPES.with(|types| {
+            types
+                .borrow()
+                .iter()
+                .find(|t| t.class == class)
+                .map(|t| (t.factory)(options))
+        })?;
+        let handle: AnyObject = class.wrap_data(
+            NativeHandleInner {
+                owner: owner.clone(),
+            },
+            &*NATIVE_HANDLE_WRAPPER,
+        );
+        self.native.push(NativeEntry {
+            owner: owner.clone(),
+            handle: handle.clone(),
+            state: Rc::new(NativeState {
+                component: RefCell::new(native),
+                started: Cell::new(false),
+                deleted: Cell::new(false),
+            }),
+        });
+        Some(handle)
+    }
+
+    /// The first component of the owner that is an instance of the class
+    pub fn get(&self, id: u64, class: &Class) -> Option<AnyObject> {
+        self.scripted
+            .get(&id)
+            .and_then(|components| {
+                components
+                    .iter()
+                    .find(|c| c.class().ancestors().contains(class))
+                    .map(|c| c.to_any_object())
+            })
+            .or_else(|| {
+                self.native
+                    .iter()
+                    .find(|e| e.owner.id() == Some(id) && e.handle.class() == *class)
+                    .map(|e| e.handle.clone())
+            })
+    }
+
+    pub fn scripted_of(&self, id: u64) -> Vec<Component> {
+        self.scripted.get(&id).cloned().unwrap_or_else(Vec::new)
+    }
+
+    /// Detaches the first component that is the given object or an instance
+    /// of the given class. The caller is responsible for `on_delete!`.
+    pub fn remove(&mut self, id: u64, component: &AnyObject) -> Option<Removed> {
+        let class = component.try_convert_to::<Class>().ok();
+        let matches = |candidate: &AnyObject| match &class {
+            Some(class) => candidate.class().ancestors().contains(class),
+            None => candidate.value() == component.value(),
+        };
+
+        if let Some(components) = self.scripted.get_mut(&id) {
+            if let Some(i) = components.iter().position(|c| matches(&c.to_any_object())) {
+                let removed = components.remove(i);
+                self.pending_starts.retain(|c| *c != removed);
+                return Some(Removed::Scripted(removed));
+            }
+        }
+        let i = self
+            .native
+            .iter()
+            .position(|e| e.owner.id() == Some(id) && matches(&e.handle))?;
+        Some(Removed::Native(self.native.remove(i)))
+    }
+
+    /// Detaches every component of a deleted owner.
+    pub fn remove_owner(&mut self, id: u64) -> Vec<Removed> {
+        let mut removed = self
+            .scripted
+            .remove(&id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(Removed::Scripted)
+            .collect::<Vec<_>>();
+        self.pending_starts
+            .retain(|c| c.owner().and_then(|o| o.id()) != Some(id));
+        let natives = self
+            .native
+            .drain_filter(|e| e.owner.id() == Some(id))
+            .map(Removed::Native);
+        removed.extend(natives);
+        removed
+    }
+
+    /// Takes the scripted components whose owners are live and have not yet
+    /// received `on_start!`.
+    pub fn take_pending_starts<F>(&mut self, is_live: F) -> Vec<Component>
+    where
+        F: Fn(u64) -> bool,
+    {
+        self.pending_starts
+            .drain_filter(|c| c.owner().and_then(|o| o.id()).map_or(false, &is_live))
+            .collect()
+    }
+
+    /// Updates the native components of every owner that has a delta this
+    /// frame. Components can add and remove others through Ruby while they
+    /// run, so they are run from a snapshot; those added start next frame.
+    pub fn update_native(&mut self, deltas: &HashMap<u64, Duration>) {
+        let running = self
+            .native
+            .iter()
+            .filter_map(|e| {
+                let delta = e.owner.id().and_then(|id| deltas.get(&id))?;
+                Some((e.owner.clone(), Rc::clone(&e.state), *delta))
+            })
+            .collect::<Vec<_>>();
+        for (owner, state, delta) in running {
+            // Components removed earlier in the loop are only held here
+            if Rc::strong_count(&state) == 1 {
+                continue;
+            }
+            let mut component = state.component.borrow_mut();
+            if !state.started.replace(true) {
+                component.on_start(&owner);
+            }
+            // A component removed by its own `on_start` doesn't update
+            if !state.deleted.get() {
+                component.update(&owner, delta);
+            }
+            if state.deleted.get() {
+                component.on_delete(&owner);
+            }
+        }
+    }
+
+    pub fn mark(&self) {
+        self.scripted.values().flatten().for_each(GC::mark);
+        self.pending_starts.iter().for_each(GC::mark);
+        self.native.iter().for_each(|e| {
+            GC::mark(&e.owner);
+            GC::mark(&e.handle);
+        });
+    }
+}
+
+pub enum Removed {
+    Scripted(Component),
+    Native(NativeEntry),
+}
+
+impl Removed {
+    pub fn on_delete(self) {
+        match self {
+            Removed::Scripted(component) => component.on_delete(),
+            Removed::Native(entry) => match entry.state.component.try_borrow_mut() {
+                Ok(mut component) => component.on_delete(&entry.owner),
+                Err(_) => entry.state.deleted.set(true),
+            },
+        }
+    }
+
+    pub fn to_any_object(&self) -> AnyObject {
+        match self {
+            Removed::Scripted(component) => component.to_any_object(),
+            Removed::Native(entry) => entry.handle.clone(),
+        }
+    }
+}
+
+impl VerifiedObject for Component {
+    fn is_correct_type<T: Object>(object: &T) -> bool {
+        object
+            .class()
+            .ancestors()
+            .contains(&Class::from_existing("Component"))
+    }
+
+    fn error_message() -> &'static str { "Object is not type of class Component" }
+}
+
+#[rustfmt::skip]
+methods!(
+    AnyObject,
+    _itself,
+
+    fn native_game_object() -> GameObject {
+        _itself.get_data(&*NATIVE_HANDLE_WRAPPER).owner.clone()
+    }
+);
+
+#[rustfmt::skip]
+methods!(
+    Component,
+    _itself,
+
+    fn empty_method() -> NilClass {
+        NilClass::new()
+    }
+);
+
+pub fn add_component_class() {
+    let mut class = Class::new("Component", None);
+    class.attr_reader("game_object");
+
+    class.def("on_start!", empty_method);
+    class.def("update!", empty_method);
+    class.def("on_delete!", empty_method);
+}
+
+/// The component store lives in `GameInner`; Ruby methods reach it through the
+/// `Game` module.
+pub fn with_store<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut ComponentStore) -> R,
+{
+    let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+    f(&mut inner.get_data_mut(&*GAME_WRAPPER).components)
+}
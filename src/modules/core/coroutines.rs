@@ -1,5 +1,12 @@
-use super::wait::WaitInner;
-use rutie::{AnyObject, Class, Object, VerifiedObject, GC};
+use super::game::GAME_WRAPPER;
+use super::game_object::GameObject;
+use super::input;
+use super::script_error::{ScriptError, ScriptPhase};
+use super::tween::Playback;
+use super::wait::{Wait, WaitInner};
+use rutie::{
+    AnyException, AnyObject, Boolean, Class, Float, Module, NilClass, Object, VerifiedObject, GC,
+};
 use std::time::Duration;
 
 wrappable_struct!(
@@ -7,27 +14,103 @@ wrappable_struct!(
     CoroutinesWrapper,
     COROUTINES_WRAPPER,
     mark(data) {
-        data.coroutines.iter().map(|c| &c.block).for_each(GC::mark);
+        data.pending
+            .iter()
+            .chain(data.coroutines.iter())
+            .for_each(Coroutine::mark);
+    }
+);
+
+wrappable_struct!(
+    HandleInner,
+    HandleWrapper,
+    HANDLE_WRAPPER,
+    mark(data) {
+        GC::mark(&data.result);
     }
 );
 
 pub struct Coroutine {
     pub wait: WaitInner,
     pub error: bool,
-    pub block: AnyObject,
+    pub body: Body,
+    pub handle: CoroutineHandle,
+}
+
+/// What a coroutine runs each time it resumes
+pub enum Body {
+    /// A Ruby `Fiber` that yields a `Wait` each time it suspends
+    Fiber(AnyObject),
+    /// A tween, which is resumed every frame until it has played out
+    Tween(Box<Playback>),
 }
 
 class!(Coroutines);
 
+impl Clone for Coroutines {
+    fn clone(&self) -> Self {
+        Coroutines {
+            value: self.value.clone(),
+        }
+    }
+}
+
 class!(CoroutineEnum);
 
+/// The Ruby-facing `Coroutine`, through which scripts can stop, pause or wait
+/// for a running coroutine
+class!(CoroutineHandle);
+
+impl Clone for CoroutineHandle {
+    fn clone(&self) -> Self {
+        CoroutineHandle {
+            value: self.value.clone(),
+        }
+    }
+}
+
 pub struct CoroutinesInner {
     pending: Vec<Coroutine>,
     pub coroutines: Vec<Coroutine>,
+    /// Bumped by `stop_all`, so that coroutines taken out to run can tell
+    generation: u64,
+}
+
+pub struct HandleInner {
+    stopped: bool,
+    paused: bool,
+    finished: bool,
+    remaining: f32,
+    /// What the coroutine's block returned, once it has finished
+    result: AnyObject,
 }
 
 impl Coroutine {
+    pub fn new(fiber: AnyObject) -> Self { Coroutine::with_class(fiber, "Coroutine") }
+
+    /// Creates the coroutine with a handle of the given `Coroutine` subclass
+    pub fn with_class(fiber: AnyObject, class: &str) -> Self {
+        Coroutine {
+            wait: WaitInner::Frames(0),
+            error: false,
+            body: Body::Fiber(fiber),
+            handle: CoroutineHandle::new(class),
+        }
+    }
+
+    pub fn tween(playback: Playback) -> Self {
+        Coroutine {
+            wait: WaitInner::Frames(0),
+            error: false,
+            body: Body::Tween(Box::new(playback)),
+            handle: CoroutineHandle::new("Coroutine"),
+        }
+    }
+
     pub fn schedule(&mut self, duration: Duration) {
+        if let Body::Tween(ref mut playback) = self.body {
+            playback.advance(duration);
+        }
         match self.wait {
             WaitInner::Seconds(ref mut seconds) => {
                 *seconds -= duration.as_secs_f32();
@@ -35,32 +118,124 @@ impl Coroutine {
             WaitInner::Frames(ref mut frames) => {
                 *frames = frames.saturating_sub(1);
             }
-            WaitInner::Done => {}
+            _ => {}
         }
     }
 
-    pub fn ready(&self) -> bool {
-        match self.wait {
+    /// Conditions given to `until` and `while` are checked every frame, and
+    /// may raise.
+    pub fn ready(&self) -> Result<bool, AnyException> {
+        Ok(match self.wait {
             WaitInner::Seconds(seconds) => seconds <= 0.0,
             WaitInner::Frames(frames) => frames == 0,
+            WaitInner::Until(ref condition) => truthy(&condition.protect_send("call", &[])?),
+            WaitInner::While(ref condition) => !truthy(&condition.protect_send("call", &[])?),
+            WaitInner::Coroutine(ref handle) => !handle.is_running(),
+            WaitInner::Event(ref event, seen) => {
+                Module::from_existing("Game")
+                    .instance_variable_get("@inner")
+                    .get_data(&*GAME_WRAPPER)
+                    .events
+                    .emitted(event)
+                    > seen
+            }
+            WaitInner::Key(ref key) => input::is_key_hit(key),
             WaitInner::Done => false,
+        })
+    }
+
+    pub fn done(&self) -> bool { matches!(self.wait, WaitInner::Done) }
+
+    /// Copies the time left before the coroutine resumes, or before its tween
+    /// finishes, to its handle
+    pub fn sync_handle(&mut self) {
+        let remaining = match (&self.body, &self.wait) {
+            (Body::Tween(playback), _) => playback.remaining(),
+            (_, WaitInner::Seconds(seconds)) => seconds.max(0.0),
+            _ => 0.0,
+        };
+        self.handle.get_data_mut(&*HANDLE_WRAPPER).remaining = remaining;
+    }
+
+    /// Anything other than a `Wait` coming back from the fiber is the value
+    /// its block finished with. Tweens finish with `nil`.
+    fn resume(&mut self, owner: Option<&GameObject>) {
+        let resumed = match self.body {
+            Body::Fiber(ref fiber) => fiber.protect_send("resume", &[]),
+            Body::Tween(ref mut playback) => match playback.play() {
+                Ok(true) => Ok(NilClass::new().to_any_object()),
+                Ok(false) => return,
+                Err(e) => Err(e),
+            },
+        };
+        match resumed {
+            Ok(yielded) => match yielded.try_convert_to::<Wait>() {
+                Ok(wait) => self.wait = wait.into(),
+                Err(_) => {
+                    self.handle.get_data_mut(&*HANDLE_WRAPPER).result = yielded;
+                    self.wait = WaitInner::Done;
+                }
+            },
+            Err(e) => self.fail(&e, owner),
         }
     }
 
-    pub fn done(&self) -> bool {
-        match self.wait {
-            WaitInner::Seconds(_) => false,
-            WaitInner::Frames(_) => false,
-            WaitInner::Done => true,
+    fn fail(&mut self, e: &AnyException, owner: Option<&GameObject>) {
+        let phase = match self.body {
+            Body::Fiber(_) => ScriptPhase::Coroutine,
+            Body::Tween(_) => ScriptPhase::Tween,
+        };
+        let error = ScriptError::new(e, phase);
+        match owner {
+            Some(owner) => error.with_object(owner).report(),
+            None => error.report(),
+        }
+        self.error = true;
+        self.wait = WaitInner::Done;
+    }
+
+    fn mark(&self) {
+        match self.body {
+            Body::Fiber(ref fiber) => GC::mark(fiber),
+            Body::Tween(ref playback) => playback.mark(),
         }
+        GC::mark(&self.handle);
+        self.wait.mark();
     }
 }
 
+impl CoroutineHandle {
+    fn new(class: &str) -> Self {
+        let inner = HandleInner {
+            stopped: false,
+            paused: false,
+            finished: false,
+            remaining: 0.0,
+            result: NilClass::new().to_any_object(),
+        };
+        Class::from_existing(class).wrap_data(inner, &*HANDLE_WRAPPER)
+    }
+
+    pub fn is_running(&self) -> bool {
+        let inner = self.get_data(&*HANDLE_WRAPPER);
+        !inner.stopped && !inner.finished
+    }
+
+    pub fn is_stopped(&self) -> bool { self.get_data(&*HANDLE_WRAPPER).stopped }
+
+    pub fn is_paused(&self) -> bool { self.get_data(&*HANDLE_WRAPPER).paused }
+
+    pub fn stop(&mut self) { self.get_data_mut(&*HANDLE_WRAPPER).stopped = true; }
+
+    fn finish(&mut self) { self.get_data_mut(&*HANDLE_WRAPPER).finished = true; }
+}
+
 impl Coroutines {
     pub fn new() -> Self {
         From::from(CoroutinesInner {
             pending: Vec::new(),
             coroutines: Vec::new(),
+            generation: 0,
         })
     }
 
@@ -70,12 +245,59 @@ impl Coroutines {
             .push(coroutine);
     }
 
+    /// Advances every coroutine by `elapsed` and resumes those that are ready.
+    /// Errors are reported against the owner, if there is one.
+    pub fn execute(&mut self, elapsed: Duration, owner: Option<&GameObject>) {
+        let generation = self.get_data(&*COROUTINES_WRAPPER).generation;
+        let mut coroutines = AsMut::<Vec<Coroutine>>::as_mut(self)
+            .drain(..)
+            .filter(|c| !c.handle.is_stopped())
+            .collect::<Vec<Coroutine>>();
+        for c in coroutines.iter_mut().filter(|c| !c.handle.is_paused()) {
+            c.schedule(elapsed);
+            match c.ready() {
+                Ok(true) => c.resume(owner),
+                Ok(false) => {}
+                Err(e) => c.fail(&e, owner),
+            }
+        }
+        if self.get_data(&*COROUTINES_WRAPPER).generation != generation {
+            coroutines.iter_mut().for_each(|c| c.handle.stop());
+        }
+        coroutines.iter_mut().for_each(|c| {
+            c.sync_handle();
+            if c.error || c.done() {
+                c.handle.finish();
+            }
+        });
+        coroutines.retain(|c| c.handle.is_running());
+        AsMut::<Vec<Coroutine>>::as_mut(self).append(&mut coroutines);
+    }
+
+    /// Stops every coroutine, including any that are running right now.
+    pub fn stop_all(&mut self) {
+        let inner = self.get_data_mut(&*COROUTINES_WRAPPER);
+        inner.generation += 1;
+        inner
+            .pending
+            .drain(..)
+            .chain(inner.coroutines.drain(..))
+            .for_each(|mut c| c.handle.stop());
+    }
+
     pub fn tidy(&mut self) {
         let inner = self.get_data_mut(&*COROUTINES_WRAPPER);
         inner.coroutines.append(&mut inner.pending);
     }
 }
 
+fn truthy(object: &AnyObject) -> bool {
+    match object.try_convert_to::<Boolean>() {
+        Ok(b) => b.to_bool(),
+        Err(_) => !object.is_nil(),
+    }
+}
+
 impl AsMut<Vec<Coroutine>> for Coroutines {
     fn as_mut(&mut self) -> &mut Vec<Coroutine> {
         &mut self.get_data_mut(&*COROUTINES_WRAPPER).coroutines
@@ -90,10 +312,88 @@ impl VerifiedObject for Coroutines {
     fn error_message() -> &'static str { "Object is not of type Coroutines" }
 }
 
+impl VerifiedObject for CoroutineHandle {
+    fn is_correct_type<T: Object>(object: &T) -> bool {
+        object
+            .class()
+            .ancestors()
+            .contains(&Class::from_existing("Coroutine"))
+    }
+
+    fn error_message() -> &'static str { "Object is not of type Coroutine" }
+}
+
 impl From<CoroutinesInner> for Coroutines {
     fn from(inner: CoroutinesInner) -> Self {
         Class::from_existing("Coroutines").wrap_data(inner, &*COROUTINES_WRAPPER)
     }
 }
 
-pub fn add_coroutines_class() { Class::new("Coroutines", None); }
+#[rustfmt::skip]
+methods!(
+    CoroutineHandle,
+    _itself,
+
+    fn stop() -> Boolean {
+        let was_running = _itself.is_running();
+        _itself.stop();
+        Boolean::new(was_running)
+    }
+
+    fn is_running() -> Boolean {
+        Boolean::new(_itself.is_running())
+    }
+
+    fn is_finished() -> Boolean {
+        Boolean::new(_itself.get_data(&*HANDLE_WRAPPER).finished)
+    }
+
+    fn is_stopped() -> Boolean {
+        Boolean::new(_itself.is_stopped())
+    }
+
+    fn is_paused() -> Boolean {
+        Boolean::new(_itself.is_paused())
+    }
+
+    fn set_paused(paused: Boolean) -> Boolean {
+        let paused = paused.map(|p| p.to_bool()).unwrap_or(false);
+        _itself.get_data_mut(&*HANDLE_WRAPPER).paused = paused;
+        Boolean::new(paused)
+    }
+
+    fn get_remaining() -> Float {
+        Float::new(_itself.get_data(&*HANDLE_WRAPPER).remaining as f64)
+    }
+
+    fn get_result() -> AnyObject {
+        _itself.get_data(&*HANDLE_WRAPPER).result.clone()
+    }
+
+    fn join() -> AnyObject {
+        let wait = WaitInner::Coroutine(_itself.clone());
+        let joined = Module::from_existing("GameUtils")
+            .protect_send("current_waiter", &[])
+            .map_or(false, |waiter| super::wait::yield_to(&waiter, wait));
+        if joined {
+            _itself.get_data(&*HANDLE_WRAPPER).result.clone()
+        } else {
+            NilClass::new().to_any_object()
+        }
+    }
+);
+
+pub fn add_coroutines_class() {
+    Class::new("Coroutines", None);
+
+    let mut class = Class::new("Coroutine", None);
+    class.def("stop", stop);
+    class.def("running?", is_running);
+    class.def("finished?", is_finished);
+    class.def("stopped?", is_stopped);
+    class.def("paused?", is_paused);
+    class.def("paused=", set_paused);
+    class.def("remaining", get_remaining);
+    class.def("result", get_result);
+    class.def("join", join);
+}
@@ -0,0 +1,145 @@
+use std::f32::consts::PI;
+
+/// Robert Penner's easing curves, each available as `in_`, `out_` and
+/// `in_out_` variants
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Curve {
+    Quad,
+    Cubic,
+    Quart,
+    Quint,
+    Sine,
+    Expo,
+    Circ,
+    Back,
+    Elastic,
+    Bounce,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ease {
+    Linear,
+    In(Curve),
+    Out(Curve),
+    InOut(Curve),
+}
+
+impl Default for Ease {
+    fn default() -> Self { Ease::Linear }
+}
+
+impl Ease {
+    /// Parses names like `linear`, `in_quad`, `out_cubic` and `in_out_bounce`
+    pub fn from_name(name: &str) -> Option<Ease> {
+        if name == "linear" {
+            return Some(Ease::Linear);
+        }
+        let (variant, curve): (fn(Curve) -> Ease, &str) = if name.starts_with("in_out_") {
+            (Ease::InOut, &name[7..])
+        } else if name.starts_with("in_") {
+            (Ease::In, &name[3..])
+        } else if name.starts_with("out_") {
+            (Ease::Out, &name[4..])
+        } else {
+            return None;
+        };
+        Curve::from_name(curve).map(variant)
+    }
+
+    /// Maps linear progress from 0 to 1 onto the curve. Back and elastic
+    /// curves overshoot that range along the way.
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Ease::Linear => t,
+            Ease::In(curve) => curve.ease_in(t),
+            Ease::Out(curve) => 1.0 - curve.ease_in(1.0 - t),
+            Ease::InOut(curve) => curve.ease_in_out(t),
+        }
+    }
+}
+
+impl Curve {
+    fn from_name(name: &str) -> Option<Curve> {
+        Some(match name {
+            "quad" => Curve::Quad,
+            "cubic" => Curve::Cubic,
+            "quart" => Curve::Quart,
+            "quint" => Curve::Quint,
+            "sine" => Curve::Sine,
+            "expo" => Curve::Expo,
+            "circ" => Curve::Circ,
+            "back" => Curve::Back,
+            "elastic" => Curve::Elastic,
+            "bounce" => Curve::Bounce,
+            _ => return None,
+        })
+    }
+
+    /// The `in_` form of the curve, from which the `out_` form is mirrored
+    fn ease_in(self, t: f32) -> f32 {
+        match self {
+            Curve::Quad => t.powi(2),
+            Curve::Cubic => t.powi(3),
+            Curve::Quart => t.powi(4),
+            Curve::Quint => t.powi(5),
+            Curve::Sine => 1.0 - (t * PI / 2.0).cos(),
+            Curve::Expo if t <= 0.0 => 0.0,
+            Curve::Expo => 2f32.powf(10.0 * t - 10.0),
+            Curve::Circ => 1.0 - (1.0 - t.powi(2)).max(0.0).sqrt(),
+            Curve::Back => {
+                let overshoot = 1.70158;
+                (overshoot + 1.0) * t.powi(3) - overshoot * t.powi(2)
+            }
+            Curve::Elastic if t <= 0.0 || t >= 1.0 => t,
+            Curve::Elastic => {
+                -(2f32.powf(10.0 * t - 10.0)) * ((10.0 * t - 10.75) * (2.0 * PI / 3.0)).sin()
+            }
+            Curve::Bounce => 1.0 - bounce_out(1.0 - t),
+        }
+    }
+
+    /// The `in_out_` form. Penner gives back and elastic curves their own
+    /// formulas, with a larger overshoot and a longer period; the others mirror
+    /// `ease_in` around the midpoint.
+    fn ease_in_out(self, t: f32) -> f32 {
+        match self {
+            Curve::Back => {
+                let overshoot = 1.70158 * 1.525;
+                let t = 2.0 * t;
+                if t < 1.0 {
+                    t.powi(2) * ((overshoot + 1.0) * t - overshoot) / 2.0
+                } else {
+                    let t = t - 2.0;
+                    (t.powi(2) * ((overshoot + 1.0) * t + overshoot) + 2.0) / 2.0
+                }
+            }
+            Curve::Elastic if t <= 0.0 || t >= 1.0 => t,
+            Curve::Elastic => {
+                let wave = ((20.0 * t - 11.125) * (2.0 * PI / 4.5)).sin();
+                if t < 0.5 {
+                    -(2f32.powf(20.0 * t - 10.0) * wave) / 2.0
+                } else {
+                    2f32.powf(10.0 - 20.0 * t) * wave / 2.0 + 1.0
+                }
+            }
+            _ if t < 0.5 => self.ease_in(2.0 * t) / 2.0,
+            _ => 1.0 - self.ease_in(2.0 - 2.0 * t) / 2.0,
+        }
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    let (n, d) = (7.5625, 2.75);
+    if t < 1.0 / d {
+        n * t * t
+    } else if t < 2.0 / d {
+        let t = t - 1.5 / d;
+        n * t * t + 0.75
+    } else if t < 2.5 / d {
+        let t = t - 2.25 / d;
+        n * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / d;
+        n * t * t + 0.984_375
+    }
+}
@@ -0,0 +1,143 @@
+use super::game::GAME_WRAPPER;
+use super::game_object::GameObject;
+use super::script_error::{ScriptError, ScriptPhase};
+use rutie::{AnyObject, Module, Object, GC};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub type NativeHandler = Rc<RefCell<dyn FnMut(&AnyObject)>>;
+
+/// When an emitted event reaches its subscribers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Delivery {
+    /// Before `emit` returns
+    Immediate,
+    /// At the end of the frame, in the order emitted
+    Deferred,
+}
+
+#[derive(Clone)]
+enum Handler {
+    Script(AnyObject),
+    Native(NativeHandler),
+}
+
+struct Subscription {
+    id: u64,
+    event: String,
+    owner: Option<GameObject>,
+    handler: Handler,
+}
+
+/// Named events and their subscribers. Scripted subscriptions usually belong
+/// to a game object and go away with it.
+#[derive(Default)]
+pub struct EventBus {
+    next_id: u64,
+    subscriptions: Vec<Subscription>,
+    queued: Vec<(String, AnyObject)>,
+    counts: HashMap<String, u64>,
+}
+
+impl EventBus {
+    pub fn subscribe_script(&mut self, event: &str, owner: GameObject, block: AnyObject) -> u64 {
+        self.add(event, Some(owner), Handler::Script(block))
+    }
+
+    pub fn subscribe_native(&mut self, event: &str, handler: NativeHandler) -> u64 {
+        self.add(event, None, Handler::Native(handler))
+    }
+
+    pub fn unsubscribe(&mut self, id: u64) -> bool {
+        let count = self.subscriptions.len();
+        self.subscriptions.retain(|s| s.id != id);
+        self.subscriptions.len() != count
+    }
+
+    pub fn unsubscribe_owner(&mut self, owner_id: u64, event: Option<&str>) {
+        self.subscriptions.retain(|s| {
+            s.owner.as_ref().and_then(GameObject::id) != Some(owner_id)
+                || event.map_or(false, |e| s.event != e)
+        });
+    }
+
+    /// How many times the event has been delivered
+    pub fn emitted(&self, event: &str) -> u64 { self.counts.get(event).copied().unwrap_or(0) }
+
+    pub fn queue(&mut self, event: &str, payload: AnyObject) {
+        self.queued.push((event.to_owned(), payload));
+    }
+
+    pub fn take_queued(&mut self) -> Vec<(String, AnyObject)> { self.queued.drain(..).collect() }
+
+    pub fn mark(&self) {
+        self.subscriptions.iter().for_each(|s| {
+            s.owner.iter().for_each(GC::mark);
+            if let Handler::Script(block) = &s.handler {
+                GC::mark(block);
+            }
+        });
+        self.queued.iter().for_each(|(_, payload)| GC::mark(payload));
+    }
+
+    /// Handlers are collected up front, since they may subscribe or
+    /// unsubscribe while the event is delivered. Inactive owners are skipped.
+    fn handlers_for(&self, event: &str) -> Vec<(Option<GameObject>, Handler)> {
+        self.subscriptions
+            .iter()
+            .filter(|s| s.event == event)
+            .filter(|s| s.owner.as_ref().map_or(true, GameObject::is_active))
+            .map(|s| (s.owner.clone(), s.handler.clone()))
+            .collect()
+    }
+
+    fn add(&mut self, event: &str, owner: Option<GameObject>, handler: Handler) -> u64 {
+        self.next_id += 1;
+        self.subscriptions.push(Subscription {
+            id: self.next_id,
+            event: event.to_owned(),
+            owner,
+            handler,
+        });
+        self.next_id
+    }
+}
+
+/// Delivers the event to its current subscribers.
+pub fn dispatch(event: &str, payload: &AnyObject) {
+    let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+    let events = &mut inner.get_data_mut(&*GAME_WRAPPER).events;
+    *events.counts.entry(event.to_owned()).or_insert(0) += 1;
+    let handlers = events.handlers_for(event);
+    for (owner, handler) in handlers {
+        match handler {
+            Handler::Script(block) => {
+                if let Err(e) = block.protect_send("call", &[payload.clone()]) {
+                    let error = ScriptError::new(&e, ScriptPhase::Event);
+                    match owner {
+                        Some(owner) => error.with_object(&owner).report(),
+                        None => error.report(),
+                    }
+                }
+            }
+            Handler::Native(handler) => match handler.try_borrow_mut() {
+                Ok(mut handler) => (&mut *handler)(payload),
+                Err(_) => warn!("Skipped re-entrant delivery of event {}", event),
+            },
+        }
+    }
+}
+
+pub fn emit(event: &str, payload: AnyObject, delivery: Delivery) {
+    match delivery {
+        Delivery::Immediate => dispatch(event, &payload),
+        Delivery::Deferred => {
+            let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+            inner
+                .get_data_mut(&*GAME_WRAPPER)
+                .events
+                .queue(event, payload);
+        }
+    }
+}
@@ -1,12 +1,33 @@
-use super::coroutines::Coroutines;
+use super::clock::GameClock;
+use super::component::ComponentStore;
+use super::coroutines::{Coroutine, CoroutineHandle, Coroutines};
+use super::events::{self, Delivery, EventBus};
 use super::game_object::GameObject;
+use super::hierarchy::Hierarchy;
+use super::object_index::ObjectIndex;
+use super::pool::{pool_size, Pool};
+use super::scene::{Scene, SceneChange};
+use super::script_error::ScriptError;
+use super::timer;
+use super::transform_store::TransformStore;
+use super::tween::{self, Tween};
 use super::vector::VectorData;
 use crate::ext::AnyNumber;
+use crate::modules::FixedTimestep;
 use nalgebra::Vector2;
-use quanta::{Clock, Instant};
-use rutie::{AnyObject, Boolean, Class, Float, Integer, Module, Object, GC};
+use quanta::Instant;
+use rutie::{
+    AnyObject, Array, Boolean, Class, Float, Hash, Integer, Module, NilClass, Object, Symbol, GC,
+    VM,
+};
+use std::sync::Arc;
 use std::time::Duration;
 
+const DEFAULT_UPDATE_GROUPS: [&str; 3] = ["input", "logic", "late"];
+const DEFAULT_UPDATE_GROUP: &str = "logic";
+/// Keeps scaled frame deltas within what `Duration` can represent
+const MAX_TIME_SCALE: f32 = 100.0;
+
 wrappable_struct!(
     GameInner,
     GameWrapper,
@@ -14,7 +35,19 @@ wrappable_struct!(
     mark(data) {
         data.pending_creates.iter().for_each(GC::mark);
         data.pending_deletes.iter().for_each(GC::mark);
+        data.pending_spawns.iter().for_each(GC::mark);
         data.game_objects.iter().for_each(GC::mark);
+        data.hierarchy.mark();
+        data.components.mark();
+        data.transforms.mark();
+        data.events.mark();
+        GC::mark(&data.coroutines);
+        data.on_quit.iter().for_each(GC::mark);
+        data.scenes.iter().for_each(GC::mark);
+        data.pending_scene_changes
+            .iter()
+            .filter_map(SceneChange::scene)
+            .for_each(GC::mark);
     }
 );
 
@@ -24,17 +57,43 @@ pub struct GameInner {
     pub camera: Vector2<f32>,
     pub pending_creates: Vec<GameObject>,
     pub pending_deletes: Vec<GameObject>,
+    pub pending_spawns: Vec<GameObject>,
     pub game_objects: Vec<GameObject>,
+    pub index: ObjectIndex,
+    pub hierarchy: Hierarchy,
+    pub components: ComponentStore,
+    pub transforms: TransformStore,
+    pub events: EventBus,
+    /// Coroutines that belong to the game rather than any object
+    pub coroutines: Coroutines,
+    pub scenes: Vec<Scene>,
+    pub pending_scene_changes: Vec<SceneChange>,
+    pub on_quit: Option<AnyObject>,
+    pub quit_requested: bool,
+    pub reloading: bool,
+    pub script_errors: Vec<ScriptError>,
+    pub update_groups: Vec<String>,
     pub time: Time,
 }
 
 #[derive(Clone, Debug)]
 pub struct Time {
-    pub clock: Clock,
+    pub clock: Arc<dyn GameClock>,
     pub frame: u64,
+    /// Frame delta after applying the time scale, or zero while paused
     pub delta: Duration,
+    pub unscaled_delta: Duration,
+    /// Scaled time since the game started
+    pub elapsed: Duration,
+    pub scale: f32,
+    pub paused: bool,
     pub now: Instant,
     pub start: Instant,
+    pub fixed_delta: Option<Duration>,
+    pub max_fixed_steps: u32,
+    pub accumulator: Duration,
+    pub fixed_steps: u32,
+    pub interpolation: f32,
 }
 
 #[rustfmt::skip]
@@ -55,10 +114,18 @@ methods!(
 
     fn create_object(object: GameObject) -> GameObject {
         let mut object = object.unwrap();
+        let mut inner = _itself.instance_variable_get("@inner");
+        let game_inner = inner.get_data_mut(&*GAME_WRAPPER);
         object.instance_variable_set("@coroutines", Coroutines::new());
-        _itself
-            .instance_variable_get("@inner")
-            .get_data_mut(&*GAME_WRAPPER).pending_creates.push(object.clone());
+        if object.id().is_none() {
+            let id = game_inner.index.next_id();
+            object.instance_variable_set("@id", Integer::from(id));
+        }
+        match game_inner.scenes.last() {
+            Some(scene) => object.instance_variable_set("@scene", scene.clone()),
+            None => object.instance_variable_set("@scene", NilClass::new()),
+        };
+        game_inner.pending_creates.push(object.clone());
         object
     }
 
@@ -73,6 +140,223 @@ methods!(
         })
     }
 
+    fn create_pool(class: Class, options: Hash) -> AnyObject {
+        let class = class.unwrap();
+        match pool_size(&class, options.ok()) {
+            Ok(size) => Pool::new(class, size).to_any_object(),
+            Err(message) => {
+                VM::raise(Class::from_existing("ArgumentError"), &message);
+                NilClass::new().to_any_object()
+            }
+        }
+    }
+
+    fn emit_event(event: Symbol, payload: AnyObject, options: Hash) -> NilClass {
+        let payload = payload.unwrap_or_else(|_| NilClass::new().to_any_object());
+        // `Game.emit(:event, immediate: true)` passes the options as the payload
+        let (payload, options) = match options {
+            Ok(options) => (payload, Some(options)),
+            Err(_) => match payload.try_convert_to::<Hash>() {
+                Ok(hash) if is_emit_options(&hash) => (NilClass::new().to_any_object(), Some(hash)),
+                _ => (payload, None),
+            },
+        };
+        let immediate = options
+            .and_then(|o| o.at(&Symbol::new("immediate")).try_convert_to::<Boolean>().ok())
+            .map_or(false, |b| b.to_bool());
+        let delivery = if immediate { Delivery::Immediate } else { Delivery::Deferred };
+        events::emit(event.unwrap().to_str(), payload, delivery);
+        NilClass::new()
+    }
+
+    fn run() -> AnyObject {
+        if !VM::is_block_given() {
+            return NilClass::new().to_any_object();
+        }
+        let block = Module::from_existing("GameUtils")
+            .protect_send("make_coroutine", &[VM::block_proc().to_any_object()])
+            .unwrap();
+        start_coroutine(&_itself, block).to_any_object()
+    }
+
+    fn run_for(duration: AnyNumber) -> AnyObject {
+        if !VM::is_block_given() {
+            return NilClass::new().to_any_object();
+        }
+        let block = Module::from_existing("GameUtils")
+            .protect_send(
+                "make_run_for",
+                &[duration.unwrap().to_any_object(), VM::block_proc().to_any_object()],
+            )
+            .unwrap();
+        start_coroutine(&_itself, block).to_any_object()
+    }
+
+    fn stop_all_coroutines() -> NilClass {
+        _itself
+            .instance_variable_get("@inner")
+            .get_data_mut(&*GAME_WRAPPER)
+            .coroutines
+            .stop_all();
+        NilClass::new()
+    }
+
+    fn after(delay: AnyNumber) -> AnyObject {
+        if !VM::is_block_given() {
+            return NilClass::new().to_any_object();
+        }
+        let delay = delay.unwrap().to_f32();
+        let block = VM::block_proc().to_any_object();
+        let mut inner = _itself.instance_variable_get("@inner");
+        let coroutines = &mut inner.get_data_mut(&*GAME_WRAPPER).coroutines;
+        timer::schedule(coroutines, delay, 0.0, Some(1), block).to_any_object()
+    }
+
+    fn every(interval: AnyNumber, options: Hash) -> AnyObject {
+        if !VM::is_block_given() {
+            return NilClass::new().to_any_object();
+        }
+        let interval = interval.unwrap().to_f32();
+        let times = match timer::times_option(options.ok()) {
+            Ok(times) => times,
+            Err(message) => {
+                VM::raise(Class::from_existing("ArgumentError"), &message);
+                return NilClass::new().to_any_object();
+            }
+        };
+        let block = VM::block_proc().to_any_object();
+        let mut inner = _itself.instance_variable_get("@inner");
+        let coroutines = &mut inner.get_data_mut(&*GAME_WRAPPER).coroutines;
+        timer::schedule(coroutines, interval, interval, times, block).to_any_object()
+    }
+
+    fn start_tween(target: AnyObject, attribute: Symbol, options: Hash) -> AnyObject {
+        match tween::build(target.unwrap(), attribute.unwrap(), options.ok()) {
+            Ok(tween) => run_tween(&_itself, &tween).to_any_object(),
+            Err(message) => {
+                VM::raise(Class::from_existing("ArgumentError"), &message);
+                NilClass::new().to_any_object()
+            }
+        }
+    }
+
+    fn play_tween(tween: Tween) -> AnyObject {
+        match tween {
+            Ok(tween) => run_tween(&_itself, &tween).to_any_object(),
+            Err(_) => {
+                VM::raise(Class::from_existing("ArgumentError"), "play_tween expects a Tween");
+                NilClass::new().to_any_object()
+            }
+        }
+    }
+
+    fn get_objects() -> Array {
+        _itself
+            .instance_variable_get("@inner")
+            .get_data(&*GAME_WRAPPER)
+            .index
+            .all()
+            .map(|o| o.to_any_object())
+            .collect()
+    }
+
+    fn find_object(id: Integer) -> AnyObject {
+        let id = id.map(|id| id.to_u64()).unwrap_or(0);
+        _itself
+            .instance_variable_get("@inner")
+            .get_data(&*GAME_WRAPPER)
+            .index
+            .get(id)
+            .map(|o| o.to_any_object())
+            .unwrap_or_else(|| NilClass::new().to_any_object())
+    }
+
+    fn find_all(class: Class) -> Array {
+        _itself
+            .instance_variable_get("@inner")
+            .get_data(&*GAME_WRAPPER)
+            .index
+            .of_class(&class.unwrap())
+            .iter()
+            .map(|o| o.to_any_object())
+            .collect()
+    }
+
+    fn with_tag(tag: Symbol) -> Array {
+        _itself
+            .instance_variable_get("@inner")
+            .get_data(&*GAME_WRAPPER)
+            .index
+            .with_tag(&tag.unwrap().to_string())
+            .iter()
+            .map(|o| o.to_any_object())
+            .collect()
+    }
+
+    fn count_objects() -> Integer {
+        Integer::new(
+            _itself
+                .instance_variable_get("@inner")
+                .get_data(&*GAME_WRAPPER)
+                .index
+                .len() as i64
+        )
+    }
+
+    fn get_scene() -> AnyObject {
+        _itself
+            .instance_variable_get("@inner")
+            .get_data(&*GAME_WRAPPER)
+            .scenes
+            .last()
+            .map(|scene| scene.to_any_object())
+            .unwrap_or_else(|| NilClass::new().to_any_object())
+    }
+
+    fn load_scene(scene: AnyObject) -> AnyObject {
+        queue_scene_change(&_itself, scene.ok(), SceneChange::Load)
+    }
+
+    fn push_scene(scene: AnyObject) -> AnyObject {
+        queue_scene_change(&_itself, scene.ok(), SceneChange::Push)
+    }
+
+    fn pop_scene() -> NilClass {
+        _itself
+            .instance_variable_get("@inner")
+            .get_data_mut(&*GAME_WRAPPER)
+            .pending_scene_changes
+            .push(SceneChange::Pop);
+        NilClass::new()
+    }
+
+    fn quit() -> NilClass {
+        _itself
+            .instance_variable_get("@inner")
+            .get_data_mut(&*GAME_WRAPPER)
+            .quit_requested = true;
+        NilClass::new()
+    }
+
+    fn on_quit() -> NilClass {
+        if VM::is_block_given() {
+            _itself
+                .instance_variable_get("@inner")
+                .get_data_mut(&*GAME_WRAPPER)
+                .on_quit = Some(VM::block_proc().to_any_object());
+        }
+        NilClass::new()
+    }
+
+    fn is_reloading() -> Boolean {
+        Boolean::new(
+            _itself
+                .instance_variable_get("@inner")
+                .get_data(&*GAME_WRAPPER)
+                .reloading
+        )
+    }
+
     fn get_frame() -> Integer {
         Integer::new(
             _itself
@@ -94,7 +378,45 @@ methods!(
         )
     }
 
+    fn get_fixed_delta_time() -> Float {
+        let inner = _itself.instance_variable_get("@inner");
+        let time = &inner.get_data(&*GAME_WRAPPER).time;
+        Float::new(time.fixed_delta.unwrap_or(time.delta).as_secs_f64())
+    }
+
+    fn get_interpolation() -> Float {
+        Float::new(
+            _itself
+                .instance_variable_get("@inner")
+                .get_data(&*GAME_WRAPPER)
+                .time
+                .interpolation as f64
+        )
+    }
+
+    fn get_unscaled_delta_time() -> Float {
+        Float::new(
+            _itself
+                .instance_variable_get("@inner")
+                .get_data(&*GAME_WRAPPER)
+                .time
+                .unscaled_delta
+                .as_secs_f64()
+        )
+    }
+
     fn get_time() -> Float {
+        Float::new(
+            _itself
+                .instance_variable_get("@inner")
+                .get_data(&*GAME_WRAPPER)
+                .time
+                .elapsed
+                .as_secs_f64()
+        )
+    }
+
+    fn get_unscaled_time() -> Float {
         let inner = _itself.instance_variable_get("@inner");
         let game_data = inner.get_data(&*GAME_WRAPPER);
         Float::new(
@@ -108,55 +430,301 @@ methods!(
 
     fn time_since(t: AnyNumber) -> Float {
         let inner = _itself.instance_variable_get("@inner");
-        let game_data = inner.get_data(&*GAME_WRAPPER);
-        let now = game_data
-            .time
-            .now
-            .saturating_duration_since(game_data.time.start)
-            .as_secs_f32();
+        let now = inner.get_data(&*GAME_WRAPPER).time.elapsed.as_secs_f32();
         Float::new((now - t.unwrap().to_f32()) as f64)
     }
+
+    fn get_time_scale() -> Float {
+        Float::new(
+            _itself
+                .instance_variable_get("@inner")
+                .get_data(&*GAME_WRAPPER)
+                .time
+                .scale as f64
+        )
+    }
+
+    fn set_time_scale(scale: AnyNumber) -> Float {
+        let mut inner = _itself.instance_variable_get("@inner");
+        let time = &mut inner.get_data_mut(&*GAME_WRAPPER).time;
+        let scale = scale.unwrap().to_f32();
+        if scale.is_finite() {
+            time.scale = scale.clamp(0.0, MAX_TIME_SCALE);
+        } else {
+            warn!("Ignoring time scale {}, which is not finite", scale);
+        }
+        Float::new(time.scale as f64)
+    }
+
+    fn is_paused() -> Boolean {
+        Boolean::new(
+            _itself
+                .instance_variable_get("@inner")
+                .get_data(&*GAME_WRAPPER)
+                .time
+                .paused
+        )
+    }
+
+    fn set_paused(paused: Boolean) -> Boolean {
+        let paused = paused.map(|p| p.to_bool()).unwrap_or(false);
+        _itself
+            .instance_variable_get("@inner")
+            .get_data_mut(&*GAME_WRAPPER)
+            .time
+            .paused = paused;
+        Boolean::new(paused)
+    }
+
+    fn get_update_groups() -> Array {
+        _itself
+            .instance_variable_get("@inner")
+            .get_data(&*GAME_WRAPPER)
+            .update_groups
+            .iter()
+            .map(|g| Symbol::new(g).to_any_object())
+            .collect()
+    }
+
+    fn set_update_groups(groups: Array) -> Array {
+        let groups = groups
+            .unwrap()
+            .into_iter()
+            .filter_map(|g| g.try_convert_to::<Symbol>().ok())
+            .map(|g| g.to_str().to_owned())
+            .collect::<Vec<_>>();
+        let array = groups.iter().map(|g| Symbol::new(g).to_any_object()).collect();
+        _itself
+            .instance_variable_get("@inner")
+            .get_data_mut(&*GAME_WRAPPER)
+            .update_groups = groups;
+        array
+    }
 );
 
+impl Time {
+    /// Moves time forward to `now`, applying the time scale.
+    pub fn advance(&mut self, now: Instant) {
+        self.frame += 1;
+        self.unscaled_delta = now - self.now;
+        self.delta = if self.paused {
+            Duration::from_millis(0)
+        } else {
+            self.unscaled_delta.mul_f32(self.scale)
+        };
+        self.elapsed += self.delta;
+        self.now = now;
+        self.accumulate();
+    }
+
+    /// Adds the frame delta to the fixed timestep accumulator and determines
+    /// how many fixed updates should run this frame. Without a fixed timestep,
+    /// a single fixed update runs per frame with the frame delta.
+    pub fn accumulate(&mut self) {
+        let fixed_delta = match self.fixed_delta {
+            Some(fixed_delta) => fixed_delta,
+            None => {
+                self.fixed_steps = 1;
+                self.interpolation = 1.0;
+                return;
+            }
+        };
+        self.accumulator += self.delta;
+        self.fixed_steps = 0;
+        while self.accumulator >= fixed_delta && self.fixed_steps < self.max_fixed_steps {
+            self.accumulator -= fixed_delta;
+            self.fixed_steps += 1;
+        }
+        if self.accumulator >= fixed_delta {
+            // Drop the backlog rather than falling further behind every frame
+            self.accumulator =
+                Duration::from_nanos((self.accumulator.as_nanos() % fixed_delta.as_nanos()) as u64);
+        }
+        self.interpolation = self.accumulator.div_duration_f32(fixed_delta);
+    }
+}
+
 impl GameInner {
-    fn new() -> Self {
-        let clock = Clock::new();
+    fn new(clock: Arc<dyn GameClock>, fixed_timestep: Option<FixedTimestep>) -> Self {
         let last_instant = clock.now();
         GameInner {
             camera: Vector2::new(0.0, 0.0),
             pending_creates: Vec::new(),
             pending_deletes: Vec::new(),
+            pending_spawns: Vec::new(),
             game_objects: Vec::new(),
+            index: ObjectIndex::default(),
+            hierarchy: Hierarchy::default(),
+            components: ComponentStore::default(),
+            transforms: TransformStore::default(),
+            events: EventBus::default(),
+            coroutines: Coroutines::new(),
+            scenes: Vec::new(),
+            pending_scene_changes: Vec::new(),
+            on_quit: None,
+            quit_requested: false,
+            reloading: false,
+            script_errors: Vec::new(),
+            update_groups: DEFAULT_UPDATE_GROUPS.iter().map(|&g| g.to_owned()).collect(),
             time: Time {
                 clock,
                 frame: 0,
                 delta: Duration::from_millis(0),
+                unscaled_delta: Duration::from_millis(0),
+                elapsed: Duration::from_millis(0),
+                scale: 1.0,
+                paused: false,
                 now: last_instant,
                 start: last_instant,
+                fixed_delta: fixed_timestep
+                    .map(|f| Duration::from_secs_f64(1.0 / f64::from(f.rate.max(1)))),
+                max_fixed_steps: fixed_timestep.map_or(1, |f| f.max_steps.max(1)),
+                accumulator: Duration::from_millis(0),
+                fixed_steps: 0,
+                interpolation: 1.0,
             },
         }
     }
 }
 
-pub fn add_game_module() {
+impl GameInner {
+    /// Live objects that are switched on, for modules that want to skip
+    /// inactive ones
+    pub fn active_objects(&self) -> impl Iterator<Item = &GameObject> {
+        self.game_objects.iter().filter(|o| o.is_active())
+    }
+
+    /// The objects that update this frame, by group in the configured
+    /// sequence and then by `update_order`. Ties keep creation order.
+    pub fn update_sequence(&self) -> Vec<GameObject> {
+        let mut objects = self
+            .game_objects
+            .iter()
+            .filter(|o| self.should_update(o))
+            .cloned()
+            .collect::<Vec<_>>();
+        objects.sort_by_cached_key(|o| (self.group_rank(o), o.update_order()));
+        objects
+    }
+
+    /// Objects in groups missing from the sequence update after all others
+    fn group_rank(&self, game_object: &GameObject) -> usize {
+        let group = game_object
+            .update_group()
+            .unwrap_or_else(|| DEFAULT_UPDATE_GROUP.to_owned());
+        self.update_groups
+            .iter()
+            .position(|g| *g == group)
+            .unwrap_or_else(|| self.update_groups.len())
+    }
+
+    /// Whether the game object's hooks and coroutines run this frame
+    pub fn should_update(&self, game_object: &GameObject) -> bool {
+        game_object.is_active()
+            && !self.is_scene_paused(game_object)
+            && (!self.time.paused || game_object.uses_unscaled_time())
+    }
+
+    /// The delta the game object's coroutines are scheduled with
+    pub fn delta_for(&self, game_object: &GameObject) -> Duration {
+        if game_object.uses_unscaled_time() {
+            self.time.unscaled_delta
+        } else {
+            self.time.delta
+        }
+    }
+}
+
+fn start_coroutine(game: &Game, block: AnyObject) -> CoroutineHandle {
+    let coroutine = Coroutine::new(block);
+    let handle = coroutine.handle.clone();
+    game.instance_variable_get("@inner")
+        .get_data_mut(&*GAME_WRAPPER)
+        .coroutines
+        .push(coroutine);
+    handle
+}
+
+fn run_tween(game: &Game, tween: &Tween) -> CoroutineHandle {
+    let mut inner = game.instance_variable_get("@inner");
+    tween::play(&mut inner.get_data_mut(&*GAME_WRAPPER).coroutines, tween)
+}
+
+/// Whether a hash holds only `emit`'s options rather than being the payload
+fn is_emit_options(hash: &Hash) -> bool {
+    hash.length() == 1 && !hash.at(&Symbol::new("immediate")).is_nil()
+}
+
+/// Scene changes are applied at the end of the frame so that objects are never
+/// removed from the middle of an update.
+fn queue_scene_change<F>(game: &Game, scene: Option<AnyObject>, change: F) -> AnyObject
+where
+    F: FnOnce(Scene) -> SceneChange,
+{
+    match scene.and_then(Scene::from_class_or_instance) {
+        Some(scene) => {
+            game.instance_variable_get("@inner")
+                .get_data_mut(&*GAME_WRAPPER)
+                .pending_scene_changes
+                .push(change(scene.clone()));
+            scene.to_any_object()
+        }
+        None => NilClass::new().to_any_object(),
+    }
+}
+
+pub fn add_game_module(clock: Arc<dyn GameClock>, fixed_timestep: Option<FixedTimestep>) {
     let mut module = Module::new("Game");
 
-    let inner: AnyObject =
-        Class::from_existing("Object").wrap_data(GameInner::new(), &*GAME_WRAPPER);
+    let inner: AnyObject = Class::from_existing("Object")
+        .wrap_data(GameInner::new(clock, fixed_timestep), &*GAME_WRAPPER);
     module.instance_variable_set("@inner", inner);
 
     module.def_self("camera", get_camera);
     module.def_self("frame", get_frame);
     module.def_self("delta_time", get_delta_time);
+    module.def_self("fixed_delta_time", get_fixed_delta_time);
+    module.def_self("interpolation", get_interpolation);
+    module.def_self("unscaled_delta_time", get_unscaled_delta_time);
     module.def_self("time", get_time);
+    module.def_self("unscaled_time", get_unscaled_time);
     module.def_self("time_since", time_since);
+    module.def_self("time_scale", get_time_scale);
+    module.def_self("paused?", is_paused);
+    module.def_self("reloading?", is_reloading);
 
     module.def_self("camera=", set_camera);
+    module.def_self("time_scale=", set_time_scale);
+    module.def_self("paused=", set_paused);
+    module.def_self("update_groups", get_update_groups);
+    module.def_self("update_groups=", set_update_groups);
+
+    module.def_self("scene", get_scene);
+    module.def_self("load_scene", load_scene);
+    module.def_self("push_scene", push_scene);
+    module.def_self("pop_scene", pop_scene);
 
     // module.def_self("axis_direction", axis_direction);
     // module.def_self("key_down", key_down);
     // module.def_self("key_hit", key_hit);
 
+    module.def_self("objects", get_objects);
+    module.def_self("find", find_object);
+    module.def_self("find_all", find_all);
+    module.def_self("with_tag", with_tag);
+    module.def_self("count", count_objects);
+
     module.def_self("create!", create_object);
     module.def_self("delete!", delete_object);
+    module.def_self("pool", create_pool);
+    module.def_self("emit", emit_event);
+    module.def_self("run!", run);
+    module.def_self("run_for!", run_for);
+    module.def_self("stop_all_coroutines", stop_all_coroutines);
+    module.def_self("after", after);
+    module.def_self("every", every);
+    module.def_self("tween", start_tween);
+    module.def_self("play_tween", play_tween);
+    module.def_self("quit!", quit);
+    module.def_self("on_quit", on_quit);
 }
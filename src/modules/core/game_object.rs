@@ -1,8 +1,18 @@
-use super::coroutines::{Coroutine, Coroutines};
+use super::component::with_store;
+use super::coroutines::{Coroutine, CoroutineHandle, Coroutines};
 use super::game::GAME_WRAPPER;
-use super::wait::{Wait, WaitInner};
+use super::script_error::{ScriptError, ScriptPhase};
+use super::timer;
+use super::transform::TransformData;
+use super::transform_store::with_transforms;
+use super::tween::{self, Tween};
+use super::vector::{VectorData, VECTOR_WRAPPER};
 use crate::ext::AnyNumber;
-use rutie::{Boolean, Class, Module, NilClass, Object, VerifiedObject, VM};
+use rutie::{
+    AnyObject, Array, Boolean, Class, Float, Hash, Integer, Module, NilClass, Object, Symbol,
+    VerifiedObject, VM,
+};
+use std::collections::BTreeSet;
 
 class!(GameObject);
 
@@ -18,52 +28,159 @@ impl GameObject {
     pub fn on_start(&self) {
         let result = self.protect_send("on_start!", &[]);
         if let Err(e) = result {
-            println!("on_start: {:?}", e);
+            ScriptError::new(&e, ScriptPhase::Start).with_object(self).report();
         }
     }
 
     pub fn update(&self) {
         let result = self.protect_send("update!", &[]);
         if let Err(e) = result {
-            println!("update: {:?}", e);
+            ScriptError::new(&e, ScriptPhase::Update).with_object(self).report();
+        }
+    }
+
+    pub fn fixed_update(&self) {
+        let result = self.protect_send("fixed_update!", &[]);
+        if let Err(e) = result {
+            ScriptError::new(&e, ScriptPhase::FixedUpdate).with_object(self).report();
+        }
+    }
+
+    pub fn late_update(&self) {
+        let result = self.protect_send("late_update!", &[]);
+        if let Err(e) = result {
+            ScriptError::new(&e, ScriptPhase::LateUpdate).with_object(self).report();
         }
     }
 
     pub fn on_delete(&self) {
         let result = self.protect_send("on_delete!", &[]);
         if let Err(e) = result {
-            println!("on_delete: {:?}", e);
+            ScriptError::new(&e, ScriptPhase::Delete).with_object(self).report();
         }
     }
 
+    /// The stable ID assigned when the object was passed to `Game.create!`, or
+    /// earlier when something needed to refer to it, e.g. to parent it
+    pub fn id(&self) -> Option<u64> {
+        self.instance_variable_get("@id")
+            .try_convert_to::<Integer>()
+            .ok()
+            .map(|id| id.to_u64())
+    }
+
+    /// Tags added before the object was created. The index only tracks live
+    /// objects, so that objects which are never created don't leak entries.
+    pub fn take_pending_tags(&mut self) -> BTreeSet<String> {
+        let tags = self.pending_tags();
+        self.instance_variable_set("@pending_tags", NilClass::new());
+        tags
+    }
+
+    fn pending_tags(&self) -> BTreeSet<String> {
+        self.instance_variable_get("@pending_tags")
+            .try_convert_to::<Array>()
+            .map(|tags| {
+                tags.into_iter()
+                    .filter_map(|tag| tag.try_convert_to::<Symbol>().ok())
+                    .map(|tag| tag.to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn set_pending_tags(&mut self, tags: &BTreeSet<String>) {
+        let tags: Array = tags.iter().map(|tag| Symbol::new(tag).to_any_object()).collect();
+        self.instance_variable_set("@pending_tags", tags);
+    }
+
+    pub fn ensure_id(&mut self) -> u64 {
+        match self.id() {
+            Some(id) => id,
+            None => {
+                let id = Module::from_existing("Game")
+                    .instance_variable_get("@inner")
+                    .get_data_mut(&*GAME_WRAPPER)
+                    .index
+                    .next_id();
+                self.instance_variable_set("@id", Integer::from(id));
+                id
+            }
+        }
+    }
+
+    /// Inactive objects keep their state but are skipped by the update loop
+    pub fn is_active(&self) -> bool {
+        self.instance_variable_get("@active")
+            .try_convert_to::<Boolean>()
+            .map(|b| b.to_bool())
+            .unwrap_or(true)
+    }
+
+    /// Orders objects within their update group, lowest first
+    pub fn update_order(&self) -> i64 {
+        self.instance_variable_get("@update_order")
+            .try_convert_to::<Integer>()
+            .map(|i| i.to_i64())
+            .unwrap_or(0)
+    }
+
+    pub fn update_group(&self) -> Option<String> {
+        self.instance_variable_get("@update_group")
+            .try_convert_to::<Symbol>()
+            .map(|s| s.to_str().to_owned())
+            .ok()
+    }
+
+    /// Whether the object keeps running while the game is paused and ignores
+    /// the time scale
+    pub fn uses_unscaled_time(&self) -> bool {
+        self.instance_variable_get("@unscaled_time")
+            .try_convert_to::<Boolean>()
+            .map(|b| b.to_bool())
+            .unwrap_or(false)
+    }
+
     pub fn execute_coroutines(&self) {
         let game_inner = Module::from_existing("Game").instance_variable_get("@inner");
-        let elapsed = game_inner.get_data(&*GAME_WRAPPER).time.delta;
-        let mut coroutines_data = self
+        let elapsed = game_inner.get_data(&*GAME_WRAPPER).delta_for(self);
+        self.instance_variable_get("@coroutines")
+            .try_convert_to::<Coroutines>()
+            .unwrap()
+            .execute(elapsed, Some(self));
+    }
+
+    fn start_coroutine(&mut self, block: AnyObject) -> CoroutineHandle {
+        let mut coroutines = self.coroutines();
+        let coroutine = Coroutine::new(block);
+        let handle = coroutine.handle.clone();
+        coroutines.push(coroutine);
+        self.instance_variable_set("@coroutines", coroutines);
+        handle
+    }
+
+    fn run_tween(&mut self, tween: &Tween) -> CoroutineHandle {
+        let mut coroutines = self.coroutines();
+        let handle = tween::play(&mut coroutines, tween);
+        self.instance_variable_set("@coroutines", coroutines);
+        handle
+    }
+
+    /// Stops the object's coroutines and timers, so that anything waiting on
+    /// them carries on
+    pub fn stop_coroutines(&self) {
+        if let Ok(mut coroutines) = self
             .instance_variable_get("@coroutines")
             .try_convert_to::<Coroutines>()
-            .unwrap();
-        let mut coroutines = AsMut::<Vec<Coroutine>>::as_mut(&mut coroutines_data)
-            .drain(..)
-            .collect::<Vec<Coroutine>>();
-        coroutines.iter_mut().for_each(|c| c.schedule(elapsed));
-        coroutines.iter_mut().filter(|c| c.ready()).for_each(|c| {
-            let yielded = c.block.protect_send("next", &[]);
-            match yielded {
-                Ok(yielded) => {
-                    let wait = yielded.try_convert_to::<Wait>().unwrap().into();
-                    c.error = false;
-                    c.wait = wait;
-                }
-                Err(e) => {
-                    println!("execute_coroutines: {:?}", e);
-                    c.error = true;
-                    c.wait = WaitInner::Done;
-                }
-            }
-        });
-        coroutines.retain(|c| !c.error && !c.done());
-        AsMut::<Vec<Coroutine>>::as_mut(&mut coroutines_data).append(&mut coroutines);
+        {
+            coroutines.stop_all();
+        }
+    }
+
+    fn coroutines(&self) -> Coroutines {
+        self.instance_variable_get("@coroutines")
+            .try_convert_to::<Coroutines>()
+            .unwrap_or_else(|_| Coroutines::new())
     }
 
     pub fn tidy_coroutines(&self) {
@@ -80,52 +197,328 @@ methods!(
     GameObject,
     _itself,
 
-    fn run() -> Boolean {
-        Boolean::new(if VM::is_block_given() {
-            let block = Module::from_existing("GameUtils")
-                .protect_send("make_coroutine", &[VM::block_proc().to_any_object()])
-                .unwrap();
-            let mut coroutines = _itself
-                .instance_variable_get("@coroutines")
-                .try_convert_to::<Coroutines>()
-                .unwrap_or_else(|_| Coroutines::new());
-            coroutines.push(Coroutine {
-                wait: WaitInner::Frames(0),
-                error: false,
-                block,
-            });
-            _itself.instance_variable_set("@coroutines", coroutines);
-            true
-        } else {
-            false
-        })
-    }
-
-    fn run_for(duration: AnyNumber) -> Boolean {
-        Boolean::new(if VM::is_block_given() {
-            let block = Module::from_existing("GameUtils")
-                .protect_send(
-                    "make_run_for",
-                    &[
-                        duration.unwrap().to_any_object(),
-                        VM::block_proc().to_any_object()
-                    ]
-                )
-                .unwrap();
-            let mut coroutines = _itself
-                .instance_variable_get("@coroutines")
-                .try_convert_to::<Coroutines>()
-                .unwrap_or_else(|_| Coroutines::new());
-            coroutines.push(Coroutine {
-                wait: WaitInner::Frames(0),
-                error: false,
-                block,
-            });
-            _itself.instance_variable_set("@coroutines", coroutines);
-            true
+    fn run() -> AnyObject {
+        if !VM::is_block_given() {
+            return NilClass::new().to_any_object();
+        }
+        let block = Module::from_existing("GameUtils")
+            .protect_send("make_coroutine", &[VM::block_proc().to_any_object()])
+            .unwrap();
+        _itself.start_coroutine(block).to_any_object()
+    }
+
+    fn after(delay: AnyNumber) -> AnyObject {
+        if !VM::is_block_given() {
+            return NilClass::new().to_any_object();
+        }
+        let delay = delay.unwrap().to_f32();
+        let mut coroutines = _itself.coroutines();
+        let block = VM::block_proc().to_any_object();
+        let timer = timer::schedule(&mut coroutines, delay, 0.0, Some(1), block);
+        _itself.instance_variable_set("@coroutines", coroutines);
+        timer.to_any_object()
+    }
+
+    fn every(interval: AnyNumber, options: Hash) -> AnyObject {
+        if !VM::is_block_given() {
+            return NilClass::new().to_any_object();
+        }
+        let interval = interval.unwrap().to_f32();
+        let times = match timer::times_option(options.ok()) {
+            Ok(times) => times,
+            Err(message) => {
+                VM::raise(Class::from_existing("ArgumentError"), &message);
+                return NilClass::new().to_any_object();
+            }
+        };
+        let mut coroutines = _itself.coroutines();
+        let block = VM::block_proc().to_any_object();
+        let timer = timer::schedule(&mut coroutines, interval, interval, times, block);
+        _itself.instance_variable_set("@coroutines", coroutines);
+        timer.to_any_object()
+    }
+
+    fn run_for(duration: AnyNumber) -> AnyObject {
+        if !VM::is_block_given() {
+            return NilClass::new().to_any_object();
+        }
+        let block = Module::from_existing("GameUtils")
+            .protect_send(
+                "make_run_for",
+                &[
+                    duration.unwrap().to_any_object(),
+                    VM::block_proc().to_any_object(),
+                    Boolean::new(_itself.uses_unscaled_time()).to_any_object(),
+                ]
+            )
+            .unwrap();
+        _itself.start_coroutine(block).to_any_object()
+    }
+
+    fn stop_all_coroutines() -> NilClass {
+        _itself.stop_coroutines();
+        NilClass::new()
+    }
+
+    fn start_tween(target: AnyObject, attribute: Symbol, options: Hash) -> AnyObject {
+        match tween::build(target.unwrap(), attribute.unwrap(), options.ok()) {
+            Ok(tween) => _itself.run_tween(&tween).to_any_object(),
+            Err(message) => {
+                VM::raise(Class::from_existing("ArgumentError"), &message);
+                NilClass::new().to_any_object()
+            }
+        }
+    }
+
+    fn play_tween(tween: Tween) -> AnyObject {
+        match tween {
+            Ok(tween) => _itself.run_tween(&tween).to_any_object(),
+            Err(_) => {
+                VM::raise(Class::from_existing("ArgumentError"), "play_tween expects a Tween");
+                NilClass::new().to_any_object()
+            }
+        }
+    }
+
+    fn add_tag(tag: Symbol) -> Boolean {
+        let tag = tag.unwrap().to_string();
+        let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+        let index = &mut inner.get_data_mut(&*GAME_WRAPPER).index;
+        match _itself.id() {
+            Some(id) if index.get(id).is_some() => Boolean::new(index.add_tag(id, tag)),
+            id => {
+                // Pooled objects keep their indexed tags while despawned
+                let indexed = id.map_or(false, |id| index.has_tag(id, &tag));
+                let mut tags = _itself.pending_tags();
+                let added = tags.insert(tag) && !indexed;
+                _itself.set_pending_tags(&tags);
+                Boolean::new(added)
+            }
+        }
+    }
+
+    fn remove_tag(tag: Symbol) -> Boolean {
+        let tag = tag.unwrap().to_string();
+        let mut tags = _itself.pending_tags();
+        let pending = tags.remove(&tag);
+        if pending {
+            _itself.set_pending_tags(&tags);
+        }
+        let indexed = _itself.id().map_or(false, |id| {
+            Module::from_existing("Game")
+                .instance_variable_get("@inner")
+                .get_data_mut(&*GAME_WRAPPER)
+                .index
+                .remove_tag(id, &tag)
+        });
+        Boolean::new(pending || indexed)
+    }
+
+    fn is_tagged(tag: Symbol) -> Boolean {
+        let tag = tag.unwrap().to_string();
+        let indexed = _itself.id().map_or(false, |id| {
+            Module::from_existing("Game")
+                .instance_variable_get("@inner")
+                .get_data(&*GAME_WRAPPER)
+                .index
+                .has_tag(id, &tag)
+        });
+        Boolean::new(indexed || _itself.pending_tags().contains(&tag))
+    }
+
+    fn get_tags() -> Array {
+        let mut tags = _itself.pending_tags();
+        if let Some(id) = _itself.id() {
+            tags.extend(
+                Module::from_existing("Game")
+                    .instance_variable_get("@inner")
+                    .get_data(&*GAME_WRAPPER)
+                    .index
+                    .tags_of(id)
+            );
+        }
+        tags.iter().map(|tag| Symbol::new(tag).to_any_object()).collect()
+    }
+
+    fn add_child(child: GameObject) -> AnyObject {
+        let mut child = child.unwrap();
+        let parent_id = _itself.ensure_id();
+        child.ensure_id();
+        let attached = Module::from_existing("Game")
+            .instance_variable_get("@inner")
+            .get_data_mut(&*GAME_WRAPPER)
+            .hierarchy
+            .attach(&_itself, parent_id, &child);
+        if attached {
+            child.to_any_object()
         } else {
-            false
-        })
+            NilClass::new().to_any_object()
+        }
+    }
+
+    fn remove_child(child: GameObject) -> Boolean {
+        let child = child.unwrap();
+        let (parent_id, child_id) = match (_itself.id(), child.id()) {
+            (Some(parent_id), Some(child_id)) => (parent_id, child_id),
+            _ => return Boolean::new(false),
+        };
+        let mut game_inner = Module::from_existing("Game").instance_variable_get("@inner");
+        let hierarchy = &mut game_inner.get_data_mut(&*GAME_WRAPPER).hierarchy;
+        let is_child = hierarchy.parent_of(child_id).and_then(GameObject::id) == Some(parent_id);
+        if is_child {
+            hierarchy.detach(child_id);
+        }
+        Boolean::new(is_child)
+    }
+
+    fn get_parent() -> AnyObject {
+        let game_inner = Module::from_existing("Game").instance_variable_get("@inner");
+        _itself
+            .id()
+            .and_then(|id| game_inner.get_data(&*GAME_WRAPPER).hierarchy.parent_of(id))
+            .map(|parent| parent.to_any_object())
+            .unwrap_or_else(|| NilClass::new().to_any_object())
+    }
+
+    fn get_children() -> Array {
+        let game_inner = Module::from_existing("Game").instance_variable_get("@inner");
+        _itself
+            .id()
+            .map(|id| game_inner.get_data(&*GAME_WRAPPER).hierarchy.children_of(id).to_vec())
+            .unwrap_or_else(Vec::new)
+            .iter()
+            .map(|child| child.to_any_object())
+            .collect()
+    }
+
+    fn get_world_position() -> VectorData {
+        let game_inner = Module::from_existing("Game").instance_variable_get("@inner");
+        let game = game_inner.get_data(&*GAME_WRAPPER);
+        From::from(game.hierarchy.world_transform(&game.transforms, &_itself).position)
+    }
+
+    fn get_world_rotation() -> Float {
+        let game_inner = Module::from_existing("Game").instance_variable_get("@inner");
+        let game = game_inner.get_data(&*GAME_WRAPPER);
+        Float::new(game.hierarchy.world_transform(&game.transforms, &_itself).rotation as f64)
+    }
+
+    fn get_world_scale() -> VectorData {
+        let game_inner = Module::from_existing("Game").instance_variable_get("@inner");
+        let game = game_inner.get_data(&*GAME_WRAPPER);
+        From::from(game.hierarchy.world_transform(&game.transforms, &_itself).scale)
+    }
+
+    fn get_transform() -> AnyObject {
+        _itself
+            .id()
+            .and_then(|id| with_transforms(|store| store.handle(id)))
+            .map(|handle| handle.to_any_object())
+            .unwrap_or_else(|| NilClass::new().to_any_object())
+    }
+
+    fn set_transform(transform: TransformData) -> TransformData {
+        let transform = transform.unwrap().get();
+        let id = _itself.ensure_id();
+        with_transforms(|store| store.set_transform(id, transform))
+    }
+
+    fn get_velocity() -> AnyObject {
+        _itself
+            .id()
+            .and_then(|id| with_transforms(|store| store.velocity(id)))
+            .map(|v| VectorData::from(v).to_any_object())
+            .unwrap_or_else(|| NilClass::new().to_any_object())
+    }
+
+    fn set_velocity(velocity: VectorData) -> VectorData {
+        let velocity = velocity.unwrap();
+        let id = _itself.ensure_id();
+        let v = velocity.get_data(&*VECTOR_WRAPPER).inner;
+        with_transforms(|store| store.set_velocity(id, v));
+        velocity
+    }
+
+    fn is_active() -> Boolean {
+        Boolean::new(_itself.is_active())
+    }
+
+    fn set_active(active: Boolean) -> Boolean {
+        let active = active.map_or(false, |a| a.to_bool());
+        if active != _itself.is_active() {
+            _itself.instance_variable_set("@active", Boolean::new(active));
+            let (hook, phase) = if active {
+                ("on_enable!", ScriptPhase::Enable)
+            } else {
+                ("on_disable!", ScriptPhase::Disable)
+            };
+            if let Err(e) = _itself.protect_send(hook, &[]) {
+                ScriptError::new(&e, phase).with_object(&_itself).report();
+            }
+        }
+        Boolean::new(active)
+    }
+
+    fn subscribe(event: Symbol) -> AnyObject {
+        if !VM::is_block_given() {
+            return NilClass::new().to_any_object();
+        }
+        let event = event.unwrap();
+        let block = VM::block_proc().to_any_object();
+        _itself.ensure_id();
+        let owner = _itself.clone();
+        let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+        let events = &mut inner.get_data_mut(&*GAME_WRAPPER).events;
+        Integer::from(events.subscribe_script(event.to_str(), owner, block)).to_any_object()
+    }
+
+    fn unsubscribe(event: Symbol) -> NilClass {
+        if let Some(id) = _itself.id() {
+            let event = event.ok();
+            let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+            inner
+                .get_data_mut(&*GAME_WRAPPER)
+                .events
+                .unsubscribe_owner(id, event.as_ref().map(Symbol::to_str));
+        }
+        NilClass::new()
+    }
+
+    fn add_component(component: AnyObject, options: Hash) -> AnyObject {
+        let id = _itself.ensure_id();
+        let owner = _itself.clone();
+        with_store(|store| store.add(&owner, id, component.unwrap(), options.ok()))
+            .unwrap_or_else(|| NilClass::new().to_any_object())
+    }
+
+    fn get_component(class: Class) -> AnyObject {
+        let class = class.unwrap();
+        _itself
+            .id()
+            .and_then(|id| with_store(|store| store.get(id, &class)))
+            .unwrap_or_else(|| NilClass::new().to_any_object())
+    }
+
+    fn remove_component(component: AnyObject) -> AnyObject {
+        let component = component.unwrap();
+        match _itself.id().and_then(|id| with_store(|store| store.remove(id, &component))) {
+            Some(removed) => {
+                let object = removed.to_any_object();
+                removed.on_delete();
+                object
+            }
+            None => NilClass::new().to_any_object(),
+        }
+    }
+
+    fn get_components() -> Array {
+        _itself
+            .id()
+            .map(|id| with_store(|store| store.scripted_of(id)))
+            .unwrap_or_else(Vec::new)
+            .iter()
+            .map(|c| c.to_any_object())
+            .collect()
     }
 
     fn empty_method() -> NilClass {
@@ -148,17 +541,62 @@ pub fn add_game_object_class() {
     let mut class = Class::new("GameObject", None);
 
     class.attr_accessor("entity");
-    class.attr_accessor("transform");
-    class.attr_accessor("velocity");
+    class.def("transform", get_transform);
+    class.def("transform=", set_transform);
+    class.def("velocity", get_velocity);
+    class.def("velocity=", set_velocity);
+
+    class.attr_reader("id");
+    class.attr_reader("scene");
+    class.attr_accessor("unscaled_time");
+    class.attr_accessor("update_order");
+    class.def("active", is_active);
+    class.def("active?", is_active);
+    class.def("active=", set_active);
+    class.attr_accessor("update_group");
 
+    class.attr_accessor("body");
     class.attr_accessor("collider");
     class.attr_accessor("collision_mask");
 
+    class.def("add_child", add_child);
+    class.def("remove_child", remove_child);
+    class.def("parent", get_parent);
+    class.def("children", get_children);
+    class.def("world_position", get_world_position);
+    class.def("world_rotation", get_world_rotation);
+    class.def("world_scale", get_world_scale);
+
+    class.def("subscribe", subscribe);
+    class.def("unsubscribe", unsubscribe);
+
+    class.def("add_component", add_component);
+    class.def("get_component", get_component);
+    class.def("remove_component", remove_component);
+    class.def("components", get_components);
+
+    class.def("add_tag", add_tag);
+    class.def("remove_tag", remove_tag);
+    class.def("tagged?", is_tagged);
+    class.def("tags", get_tags);
+
     class.def("run!", run);
     class.def("run_for!", run_for);
+    class.def("stop_all_coroutines", stop_all_coroutines);
+    class.def("after", after);
+    class.def("every", every);
+    class.def("tween", start_tween);
+    class.def("play_tween", play_tween);
 
     class.def("on_start!", empty_method);
     class.def("update!", empty_method);
+    class.def("late_update!", empty_method);
+    class.def("fixed_update!", empty_method);
     class.def("on_delete!", empty_method);
+    class.def("on_reuse!", empty_method);
+    class.def("on_enable!", empty_method);
+    class.def("on_disable!", empty_method);
+    class.def("on_collision_enter!", empty_method);
     class.def("on_collision!", empty_method);
+    class.def("on_collision_exit!", empty_method);
 }
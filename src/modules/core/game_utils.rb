@@ -2,24 +2,49 @@
 
 # Base game utilities
 module GameUtils
+  # The waiter of the coroutine running on this fiber, if any
+  def self.current_waiter
+    Thread.current[:dathos_waiter]
+  end
+
+  def self.make_waiter
+    Thread.current[:dathos_waiter] = Waiter.new
+  end
+
   def self.make_coroutine(block)
-    Enumerator.new do |yielder|
-      wait = Waiter.new(yielder)
-      block.call(wait)
-      wait.done
+    Fiber.new do
+      block.call(make_waiter)
     end
   end
 
-  def self.make_run_for(duration, block)
-    Enumerator.new do |yielder|
-      wait = Waiter.new(yielder)
-      started = Game.time
+  def self.make_timer(interval, times, block)
+    Fiber.new do
+      wait = make_waiter
+      count = 0
       loop do
-        time_since = Game.time_since started
+        block.call
+        count += 1
+        break if times && count >= times
+
+        wait.for_seconds(interval)
+      end
+      nil
+    end
+  end
+
+  def self.make_run_for(duration, block, unscaled = false)
+    Fiber.new do
+      wait = make_waiter
+      clock = unscaled ? -> { Game.unscaled_time } : -> { Game.time }
+      started = clock.call
+      loop do
+        time_since = clock.call - started
         block.call(time_since, duration)
-        time_since > duration ? wait.done : wait.next_frame
+        break if time_since > duration
+
+        wait.next_frame
       end
-      wait.done
+      nil
     end
   end
 end
@@ -0,0 +1,96 @@
+use super::game_object::GameObject;
+use super::transform::Transform;
+use super::transform_store::TransformStore;
+use rutie::GC;
+use std::collections::HashMap;
+
+/// Parent/child relationships between game objects, keyed by object ID. Each
+/// object's `transform` is relative to its parent.
+#[derive(Default)]
+pub struct Hierarchy {
+    parents: HashMap<u64, GameObject>,
+    children: HashMap<u64, Vec<GameObject>>,
+}
+
+impl Hierarchy {
+    pub fn parent_of(&self, id: u64) -> Option<&GameObject> { self.parents.get(&id) }
+
+    pub fn children_of(&self, id: u64) -> &[GameObject] {
+        self.children.get(&id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Fails if the child is the parent or one of its ancestors.
+    pub fn attach(&mut self, parent: &GameObject, parent_id: u64, child: &GameObject) -> bool {
+        let child_id = match child.id() {
+            Some(id) => id,
+            None => return false,
+        };
+        if self.ancestor_ids(parent_id).contains(&child_id) || parent_id == child_id {
+            return false;
+        }
+        self.detach(child_id);
+        self.parents.insert(child_id, parent.clone());
+        self.children
+            .entry(parent_id)
+            .or_default()
+            .push(child.clone());
+        true
+    }
+
+    pub fn detach(&mut self, child_id: u64) {
+        let parent_id = self.parents.remove(&child_id).and_then(|p| p.id());
+        if let Some(siblings) = parent_id.and_then(|id| self.children.get_mut(&id)) {
+            siblings.retain(|c| c.id() != Some(child_id));
+        }
+    }
+
+    /// Forgets the object entirely, as when it is deleted.
+    pub fn remove(&mut self, id: u64) {
+        self.detach(id);
+        for child in self.children.remove(&id).unwrap_or_default() {
+            child.id().into_iter().for_each(|child_id| {
+                self.parents.remove(&child_id);
+            });
+        }
+    }
+
+    /// Children, grandchildren and so on, parents before their children
+    pub fn descendants(&self, id: u64) -> Vec<GameObject> {
+        let mut descendants = self.children_of(id).to_vec();
+        let mut i = 0;
+        while i < descendants.len() {
+            if let Some(child_id) = descendants[i].id() {
+                descendants.extend_from_slice(self.children_of(child_id));
+            }
+            i += 1;
+        }
+        descendants
+    }
+
+    pub fn world_transform(
+        &self,
+        transforms: &TransformStore,
+        game_object: &GameObject,
+    ) -> Transform {
+        let local = transforms.local(game_object);
+        match game_object.id().and_then(|id| self.parent_of(id)) {
+            Some(parent) => self.world_transform(transforms, parent).compose(&local),
+            None => local,
+        }
+    }
+
+    pub fn mark(&self) {
+        self.parents.values().for_each(GC::mark);
+        self.children.values().flatten().for_each(GC::mark);
+    }
+
+    fn ancestor_ids(&self, id: u64) -> Vec<u64> {
+        let mut ancestors = Vec::new();
+        let mut current = self.parent_of(id).and_then(GameObject::id);
+        while let Some(ancestor) = current {
+            ancestors.push(ancestor);
+            current = self.parent_of(ancestor).and_then(GameObject::id);
+        }
+        ancestors
+    }
+}
@@ -86,6 +86,14 @@ impl InputInner {
     }
 }
 
+/// Whether the key was pressed this frame
+pub fn is_key_hit(key: &str) -> bool {
+    Module::from_existing("Input")
+        .instance_variable_get("@input")
+        .get_data(&*INPUT_WRAPPER)
+        .is_key_hit(key.to_owned())
+}
+
 fn get_current_frame() -> u64 {
     Module::from_existing("Game")
         .instance_variable_get("@inner")
@@ -0,0 +1,115 @@
+use super::game_object::GameObject;
+use rutie::{Class, Object};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Lookup tables over the live game objects, kept in step with
+/// `GameInner::game_objects` so that queries don't have to ask Ruby about
+/// every object.
+#[derive(Default)]
+pub struct ObjectIndex {
+    next_id: u64,
+    by_id: BTreeMap<u64, GameObject>,
+    by_class: Vec<(Class, BTreeSet<u64>)>,
+    tags: HashMap<u64, BTreeSet<String>>,
+    by_tag: HashMap<String, BTreeSet<u64>>,
+}
+
+impl ObjectIndex {
+    pub fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Adds the object to queries, along with any tags it was given before
+    pub fn insert(&mut self, id: u64, mut game_object: GameObject) {
+        for tag in game_object.take_pending_tags() {
+            self.add_tag(id, tag);
+        }
+        let class = game_object.class();
+        match self.by_class.iter_mut().find(|(c, _)| *c == class) {
+            Some((_, ids)) => {
+                ids.insert(id);
+            }
+            None => self.by_class.push((class, vec![id].into_iter().collect())),
+        }
+        self.by_id.insert(id, game_object);
+    }
+
+    pub fn remove(&mut self, id: u64) {
+        self.hide(id);
+        for tag in self.tags.remove(&id).unwrap_or_default() {
+            if let Some(ids) = self.by_tag.get_mut(&tag) {
+                ids.remove(&id);
+            }
+        }
+    }
+
+    /// Takes the object out of queries but keeps its tags, as when it returns
+    /// to a pool.
+    pub fn hide(&mut self, id: u64) {
+        self.by_id.remove(&id);
+        self.by_class.iter_mut().for_each(|(_, ids)| {
+            ids.remove(&id);
+        });
+        self.by_class.retain(|(_, ids)| !ids.is_empty());
+    }
+
+    pub fn len(&self) -> usize { self.by_id.len() }
+
+    pub fn get(&self, id: u64) -> Option<&GameObject> { self.by_id.get(&id) }
+
+    /// Live objects in the order they were created
+    pub fn all(&self) -> impl Iterator<Item = &GameObject> { self.by_id.values() }
+
+    /// Live objects that are instances of the class or one of its subclasses
+    pub fn of_class(&self, class: &Class) -> Vec<GameObject> {
+        self.by_class
+            .iter()
+            .filter(|(c, _)| c.ancestors().contains(class))
+            .flat_map(|(_, ids)| ids.iter())
+            .copied()
+            .collect::<BTreeSet<u64>>()
+            .into_iter()
+            .filter_map(|id| self.by_id.get(&id).cloned())
+            .collect()
+    }
+
+    pub fn with_tag(&self, tag: &str) -> Vec<GameObject> {
+        self.by_tag
+            .get(tag)
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| self.by_id.get(id).cloned())
+                    .collect()
+            })
+            .unwrap_or_else(Vec::new)
+    }
+
+    pub fn tags_of(&self, id: u64) -> Vec<String> {
+        self.tags
+            .get(&id)
+            .map(|tags| tags.iter().cloned().collect())
+            .unwrap_or_else(Vec::new)
+    }
+
+    pub fn has_tag(&self, id: u64, tag: &str) -> bool {
+        self.tags.get(&id).map(|t| t.contains(tag)).unwrap_or(false)
+    }
+
+    /// Tags of objects that aren't live yet wait on the object until it is
+    /// inserted.
+    pub fn add_tag(&mut self, id: u64, tag: String) -> bool {
+        self.by_tag.entry(tag.clone()).or_default().insert(id);
+        self.tags.entry(id).or_default().insert(tag)
+    }
+
+    pub fn remove_tag(&mut self, id: u64, tag: &str) -> bool {
+        if let Some(ids) = self.by_tag.get_mut(tag) {
+            ids.remove(&id);
+        }
+        self.tags
+            .get_mut(&id)
+            .map(|tags| tags.remove(tag))
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,192 @@
+use super::coroutines::Coroutines;
+use super::game::GAME_WRAPPER;
+use super::game_object::GameObject;
+use super::script_error::{ScriptError, ScriptPhase};
+use rutie::{Boolean, Class, Hash, Integer, Module, NilClass, Object, Symbol, VerifiedObject, GC};
+
+const DEFAULT_POOL_SIZE: usize = 16;
+
+wrappable_struct!(
+    PoolInner,
+    PoolWrapper,
+    POOL_WRAPPER,
+    mark(data) {
+        GC::mark(&data.class);
+        data.free.iter().for_each(GC::mark);
+    }
+);
+
+class!(Pool);
+
+impl Clone for Pool {
+    fn clone(&self) -> Self {
+        Pool {
+            value: self.value.clone(),
+        }
+    }
+}
+
+/// Game objects of one class that are created up front and reused, so that
+/// spawning and despawning them skips allocation and the create and delete
+/// hooks.
+pub struct PoolInner {
+    class: Class,
+    free: Vec<GameObject>,
+}
+
+impl Pool {
+    pub fn new(class: Class, size: usize) -> Pool {
+        let inner = PoolInner {
+            class,
+            free: Vec::with_capacity(size),
+        };
+        let mut pool: Pool = Class::from_existing("Pool").wrap_data(inner, &*POOL_WRAPPER);
+        for _ in 0..size {
+            let game_object = pool.allocate();
+            pool.get_data_mut(&*POOL_WRAPPER).free.push(game_object);
+        }
+        pool
+    }
+
+    /// A free object, or a new one when the pool is exhausted
+    pub fn take(&mut self) -> GameObject {
+        match self.get_data_mut(&*POOL_WRAPPER).free.pop() {
+            Some(game_object) => game_object,
+            None => self.allocate(),
+        }
+    }
+
+    /// Returns a despawned object to the pool with its coroutines cleared.
+    pub fn release(&mut self, mut game_object: GameObject) {
+        let free = &mut self.get_data_mut(&*POOL_WRAPPER).free;
+        if free.contains(&game_object) {
+            return;
+        }
+        game_object.stop_coroutines();
+        game_object.instance_variable_set("@coroutines", Coroutines::new());
+        game_object.instance_variable_set("@reused", Boolean::new(true));
+        free.push(game_object);
+    }
+
+    fn allocate(&mut self) -> GameObject {
+        let class = Class::from(self.get_data(&*POOL_WRAPPER).class.value());
+        let mut game_object = class
+            .new_instance(&[])
+            .try_convert_to::<GameObject>()
+            .expect("pooled classes are checked to be game objects");
+        game_object.instance_variable_set("@coroutines", Coroutines::new());
+        game_object.instance_variable_set("@pool", self.clone());
+        game_object.ensure_id();
+        game_object
+    }
+}
+
+impl GameObject {
+    pub fn pool(&self) -> Option<Pool> {
+        self.instance_variable_get("@pool")
+            .try_convert_to::<Pool>()
+            .ok()
+    }
+
+    /// Whether the object has been despawned at least once
+    pub fn is_reused(&self) -> bool {
+        self.instance_variable_get("@reused")
+            .try_convert_to::<Boolean>()
+            .map(|b| b.to_bool())
+            .unwrap_or(false)
+    }
+
+    pub fn on_reuse(&self) {
+        let result = self.protect_send("on_reuse!", &[]);
+        if let Err(e) = result {
+            ScriptError::new(&e, ScriptPhase::Reuse).with_object(self).report();
+        }
+    }
+}
+
+#[rustfmt::skip]
+methods!(
+    Pool,
+    _itself,
+
+    fn spawn(args: Hash) -> GameObject {
+        let mut game_object = _itself.take();
+        let mut assignments = Vec::new();
+        if let Ok(args) = args {
+            args.each(|key, value| assignments.push((key, value)));
+        }
+        for (key, value) in assignments {
+            let setter = match key.try_convert_to::<Symbol>() {
+                Ok(key) => format!("{}=", key.to_str()),
+                Err(_) => continue,
+            };
+            if let Err(e) = game_object.protect_send(&setter, &[value]) {
+                ScriptError::new(&e, ScriptPhase::Start).with_object(&game_object).report();
+            }
+        }
+
+        let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+        let game = inner.get_data_mut(&*GAME_WRAPPER);
+        match game.scenes.last() {
+            Some(scene) => game_object.instance_variable_set("@scene", scene.clone()),
+            None => game_object.instance_variable_set("@scene", NilClass::new()),
+        };
+        game.pending_spawns.push(game_object.clone());
+        game_object
+    }
+
+    fn despawn(game_object: GameObject) -> Boolean {
+        Boolean::new(match game_object {
+            Ok(game_object) if game_object.pool().as_ref() == Some(&_itself) => {
+                Module::from_existing("Game")
+                    .instance_variable_get("@inner")
+                    .get_data_mut(&*GAME_WRAPPER)
+                    .pending_deletes
+                    .push(game_object);
+                true
+            }
+            _ => false,
+        })
+    }
+
+    fn get_available() -> Integer {
+        Integer::from(_itself.get_data(&*POOL_WRAPPER).free.len() as u64)
+    }
+
+    fn get_object_class() -> Class {
+        Class::from(_itself.get_data(&*POOL_WRAPPER).class.value())
+    }
+);
+
+impl VerifiedObject for Pool {
+    fn is_correct_type<T: Object>(object: &T) -> bool {
+        object.class() == Class::from_existing("Pool")
+    }
+
+    fn error_message() -> &'static str { "Object is not type of class Pool" }
+}
+
+pub fn add_pool_class() {
+    let mut class = Class::new("Pool", None);
+    class.def("spawn", spawn);
+    class.def("despawn", despawn);
+    class.def("available", get_available);
+    class.def("object_class", get_object_class);
+}
+
+/// Checks the arguments of `Game.pool`, returning the pool's size or the
+/// message of the `ArgumentError` to raise
+pub fn pool_size(class: &Class, options: Option<Hash>) -> Result<usize, String> {
+    let game_object = Class::from_existing("GameObject");
+    if !class.ancestors().contains(&game_object) {
+        return Err("Pooled classes must inherit from GameObject".to_owned());
+    }
+    let size = options
+        .map(|o| o.at(&Symbol::new("size")))
+        .and_then(|size| size.try_convert_to::<Integer>().ok())
+        .map_or(DEFAULT_POOL_SIZE as i64, |size| size.to_i64());
+    if size <= 0 {
+        return Err(format!("Pool size must be positive, got {}", size));
+    }
+    Ok(size as usize)
+}
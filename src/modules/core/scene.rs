@@ -0,0 +1,145 @@
+use super::game::GameInner;
+use super::game_object::GameObject;
+use super::script_error::{ScriptError, ScriptPhase};
+use rutie::{AnyObject, Class, NilClass, Object, VerifiedObject};
+
+class!(Scene);
+
+impl Clone for Scene {
+    fn clone(&self) -> Self {
+        Scene {
+            value: self.value.clone(),
+        }
+    }
+}
+
+pub enum SceneChange {
+    Load(Scene),
+    Push(Scene),
+    Pop,
+}
+
+impl SceneChange {
+    pub fn scene(&self) -> Option<&Scene> {
+        match self {
+            SceneChange::Load(scene) | SceneChange::Push(scene) => Some(scene),
+            SceneChange::Pop => None,
+        }
+    }
+}
+
+impl Scene {
+    /// Accepts either a `Scene` subclass, which is instantiated, or an
+    /// instance of one.
+    pub fn from_class_or_instance(object: AnyObject) -> Option<Scene> {
+        let object = if Class::is_correct_type(&object) {
+            object
+                .try_convert_to::<Class>()
+                .ok()?
+                .new_instance(&[])
+        } else {
+            object
+        };
+        object.try_convert_to::<Scene>().ok()
+    }
+
+    pub fn on_enter(&self) { self.send_hook("on_enter!", ScriptPhase::Enter); }
+
+    pub fn on_exit(&self) { self.send_hook("on_exit!", ScriptPhase::Exit); }
+
+    pub fn on_pause(&self) { self.send_hook("on_pause!", ScriptPhase::Pause); }
+
+    pub fn on_resume(&self) { self.send_hook("on_resume!", ScriptPhase::Resume); }
+
+    /// Whether the game object was created while this scene was on top
+    pub fn owns(&self, game_object: &GameObject) -> bool {
+        game_object.instance_variable_get("@scene").value() == self.value()
+    }
+
+    fn send_hook(&self, hook: &str, phase: ScriptPhase) {
+        let result = self.protect_send(hook, &[]);
+        if let Err(e) = result {
+            ScriptError::new(&e, phase).with_object(self).report();
+        }
+    }
+}
+
+impl GameInner {
+    /// Objects in scenes below the top of the stack are paused. Objects created
+    /// outside of any scene are never paused.
+    pub fn is_scene_paused(&self, game_object: &GameObject) -> bool {
+        let scene = game_object.instance_variable_get("@scene");
+        !scene.is_nil()
+            && self
+                .scenes
+                .last()
+                .map(|top| top.value() != scene.value())
+                .unwrap_or(true)
+    }
+
+    pub fn push_scene(&mut self, scene: Scene) {
+        if let Some(top) = self.scenes.last() {
+            top.on_pause();
+        }
+        self.scenes.push(scene.clone());
+        scene.on_enter();
+    }
+
+    pub fn pop_scene(&mut self) -> Option<Scene> {
+        let scene = self.scenes.pop()?;
+        scene.on_exit();
+        self.pending_deletes
+            .extend(self.game_objects.iter().filter(|o| scene.owns(o)).cloned());
+        self.pending_creates.retain(|o| !scene.owns(o));
+        self.pending_deletes
+            .extend(self.pending_spawns.iter().filter(|o| scene.owns(o)).cloned());
+        Some(scene)
+    }
+
+    pub fn apply_scene_change(&mut self, change: SceneChange) {
+        match change {
+            SceneChange::Load(scene) => {
+                while self.pop_scene().is_some() {}
+                self.push_scene(scene);
+            }
+            SceneChange::Push(scene) => self.push_scene(scene),
+            SceneChange::Pop => {
+                if self.pop_scene().is_some() {
+                    if let Some(top) = self.scenes.last() {
+                        top.on_resume();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl VerifiedObject for Scene {
+    fn is_correct_type<T: Object>(object: &T) -> bool {
+        object
+            .class()
+            .ancestors()
+            .contains(&Class::from_existing("Scene"))
+    }
+
+    fn error_message() -> &'static str { "Object is not type of class Scene" }
+}
+
+#[rustfmt::skip]
+methods!(
+    Scene,
+    _itself,
+
+    fn empty_method() -> NilClass {
+        NilClass::new()
+    }
+);
+
+pub fn add_scene_class() {
+    let mut class = Class::new("Scene", None);
+
+    class.def("on_enter!", empty_method);
+    class.def("on_exit!", empty_method);
+    class.def("on_pause!", empty_method);
+    class.def("on_resume!", empty_method);
+}
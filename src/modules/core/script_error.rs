@@ -0,0 +1,118 @@
+use super::game::GAME_WRAPPER;
+use rutie::{AnyException, Exception, Module, Object, RString};
+use std::fmt;
+
+/// An exception raised by game script code, with enough context to find where
+/// it came from.
+#[derive(Clone, Debug)]
+pub struct ScriptError {
+    /// Class of the raised exception, e.g. `NoMethodError`
+    pub exception: String,
+    pub message: String,
+    pub backtrace: Vec<String>,
+    /// Class of the game object whose hook raised, if any
+    pub object: Option<String>,
+    pub phase: ScriptPhase,
+}
+
+/// The point in the lifecycle at which a script error was raised
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScriptPhase {
+    Load,
+    Start,
+    FixedUpdate,
+    Update,
+    LateUpdate,
+    Coroutine,
+    Tween,
+    Delete,
+    Reuse,
+    Enable,
+    Disable,
+    Event,
+    Collision,
+    Enter,
+    Exit,
+    Pause,
+    Resume,
+    Quit,
+    /// Escaped a module's hook and ended the game loop
+    Frame,
+    Shutdown,
+}
+
+impl ScriptError {
+    pub fn new(exception: &AnyException, phase: ScriptPhase) -> Self {
+        ScriptError {
+            exception: class_name(exception),
+            message: exception.message(),
+            backtrace: exception
+                .backtrace()
+                .map(|lines| {
+                    lines
+                        .into_iter()
+                        .filter_map(|line| line.try_convert_to::<RString>().ok())
+                        .map(|line| line.to_string())
+                        .collect()
+                })
+                .unwrap_or_else(Vec::new),
+            object: None,
+            phase,
+        }
+    }
+
+    /// Stands in for an exception that was raised but could not be retrieved
+    pub fn unknown(phase: ScriptPhase) -> Self {
+        ScriptError {
+            exception: "<unknown>".to_string(),
+            message: "script raised an exception that could not be read".to_string(),
+            backtrace: Vec::new(),
+            object: None,
+            phase,
+        }
+    }
+
+    pub fn with_object<O>(mut self, object: &O) -> Self
+    where
+        O: Object,
+    {
+        self.object = Some(class_name(object));
+        self
+    }
+
+    /// Logs the error and queues it for the engine's error handler.
+    pub fn report(self) {
+        error!("{}", self);
+        Module::from_existing("Game")
+            .instance_variable_get("@inner")
+            .get_data_mut(&*GAME_WRAPPER)
+            .script_errors
+            .push(self);
+    }
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.object {
+            Some(object) => write!(f, "{:?} in {}: ", self.phase, object)?,
+            None => write!(f, "{:?}: ", self.phase)?,
+        }
+        write!(f, "{} ({})", self.message, self.exception)?;
+        self.backtrace
+            .iter()
+            .try_for_each(|line| write!(f, "\n\tfrom {}", line))
+    }
+}
+
+fn class_name<O>(object: &O) -> String
+where
+    O: Object,
+{
+    object
+        .class()
+        .protect_send("name", &[])
+        .ok()
+        .and_then(|name| name.try_convert_to::<RString>().ok())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| "<anonymous>".to_string())
+}
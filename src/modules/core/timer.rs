@@ -0,0 +1,66 @@
+use super::coroutines::{Coroutine, CoroutineHandle, Coroutines};
+use super::wait::WaitInner;
+use rutie::{AnyObject, Boolean, Class, Float, Hash, Integer, Module, NilClass, Object, Symbol};
+
+/// Schedules the block to run after `delay` seconds, then every `interval`
+/// seconds until it has run `times` times, or forever without a limit. Timers
+/// are coroutines, so they follow the same time as the coroutines they are
+/// scheduled with.
+pub fn schedule(
+    coroutines: &mut Coroutines,
+    delay: f32,
+    interval: f32,
+    times: Option<u64>,
+    block: AnyObject,
+) -> CoroutineHandle {
+    let times = times.map_or_else(
+        || NilClass::new().to_any_object(),
+        |t| Integer::from(t).to_any_object(),
+    );
+    let fiber = Module::from_existing("GameUtils")
+        .protect_send(
+            "make_timer",
+            &[Float::new(interval as f64).to_any_object(), times, block],
+        )
+        .unwrap();
+    let mut coroutine = Coroutine::with_class(fiber, "Timer");
+    coroutine.wait = WaitInner::Seconds(delay);
+    coroutine.sync_handle();
+    let handle = coroutine.handle.clone();
+    coroutines.push(coroutine);
+    handle
+}
+
+/// The `times:` option of `every`, which must be at least 1 if given
+pub fn times_option(options: Option<Hash>) -> Result<Option<u64>, String> {
+    let times = options
+        .map(|o| o.at(&Symbol::new("times")))
+        .and_then(|times| times.try_convert_to::<Integer>().ok())
+        .map(|times| times.to_i64());
+    match times {
+        Some(times) if times < 1 => Err(format!("Timer times must be positive, got {}", times)),
+        times => Ok(times.map(|t| t as u64)),
+    }
+}
+
+/// Timers are coroutines whose handles can also be cancelled by that name
+pub fn add_timer_class() {
+    let mut class = Class::new("Timer", Some(&Class::from_existing("Coroutine")));
+    class.def("cancel", cancel);
+    class.def("cancelled?", is_cancelled);
+}
+
+#[rustfmt::skip]
+methods!(
+    CoroutineHandle,
+    _itself,
+
+    fn cancel() -> NilClass {
+        _itself.stop();
+        NilClass::new()
+    }
+
+    fn is_cancelled() -> Boolean {
+        Boolean::new(_itself.is_stopped())
+    }
+);
@@ -1,3 +1,4 @@
+use super::transform_store::with_transforms;
 use super::vector::VectorData;
 use crate::ext::{AnyNumber, RotationDirection, RotationExt};
 use nalgebra::Vector2;
@@ -14,19 +15,99 @@ pub struct Transform {
 
 class!(TransformData);
 
+impl Clone for TransformData {
+    fn clone(&self) -> Self {
+        TransformData {
+            value: self.value.clone(),
+        }
+    }
+}
+
+/// A transform value, or a handle to a game object's transform in the
+/// `TransformStore`
 pub struct TransformInner {
     pub inner: Transform,
+    pub owner: Option<u64>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform {
+            position: Vector2::new(0.0, 0.0),
+            rotation: 0.0,
+            scale: Vector2::new(1.0, 1.0),
+        }
+    }
+}
+
+impl Transform {
+    /// Applies a transform relative to this one, e.g. a child's local
+    /// transform to its parent's world transform.
+    pub fn compose(&self, local: &Transform) -> Transform {
+        let scaled = local.position.component_mul(&self.scale);
+        let (sin, cos) = self.rotation.sin_cos();
+        Transform {
+            position: self.position
+                + Vector2::new(
+                    scaled.x * cos - scaled.y * sin,
+                    scaled.x * sin + scaled.y * cos,
+                ),
+            rotation: (self.rotation + local.rotation).normalize(),
+            scale: self.scale.component_mul(&local.scale),
+        }
+    }
+}
+
+impl TransformData {
+    pub fn handle(id: u64) -> Self {
+        let inner = TransformInner {
+            inner: Transform::default(),
+            owner: Some(id),
+        };
+        Class::from_existing("Transform").wrap_data(inner, &*TRANSFORM_WRAPPER)
+    }
+
+    /// Turns a handle back into a plain value once its object is gone
+    pub fn detach(&mut self, last: Transform) {
+        let data = self.get_data_mut(&*TRANSFORM_WRAPPER);
+        data.inner = last;
+        data.owner = None;
+    }
+
+    pub fn get(&self) -> Transform {
+        let data = self.get_data(&*TRANSFORM_WRAPPER);
+        data.owner
+            .and_then(|id| with_transforms(|store| store.get(id).copied()))
+            .unwrap_or(data.inner)
+    }
+
+    pub fn modify<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Transform) -> R,
+    {
+        let data = self.get_data_mut(&*TRANSFORM_WRAPPER);
+        match data.owner {
+            Some(id) => with_transforms(|store| match store.get_mut(id) {
+                Some(transform) => f(transform),
+                None => f(&mut data.inner),
+            }),
+            None => f(&mut data.inner),
+        }
+    }
 }
 
 impl From<Transform> for TransformData {
     fn from(t: Transform) -> Self {
-        Class::from_existing("Transform")
-            .wrap_data(TransformInner { inner: t }, &*TRANSFORM_WRAPPER)
+        let inner = TransformInner {
+            inner: t,
+            owner: None,
+        };
+        Class::from_existing("Transform").wrap_data(inner, &*TRANSFORM_WRAPPER)
     }
 }
 
 impl Into<Transform> for TransformData {
-    fn into(self) -> Transform { self.get_data(&*TRANSFORM_WRAPPER).inner }
+    fn into(self) -> Transform { self.get() }
 }
 
 #[rustfmt::skip]
@@ -59,62 +140,73 @@ methods!(
     }
 
     fn set_position(p: VectorData) -> VectorData {
-        let mut transform = _itself.get_data_mut(&*TRANSFORM_WRAPPER);
-        transform.inner.position = p.unwrap().into();
-        From::from(transform.inner.position)
+        let p = p.unwrap().into();
+        From::from(_itself.modify(|t| {
+            t.position = p;
+            t.position
+        }))
     }
 
     fn set_rotation(r: AnyNumber) -> Float {
-        let mut transform = _itself.get_data_mut(&*TRANSFORM_WRAPPER);
-        transform.inner.rotation = r.unwrap().to_f32();
-        Float::new(transform.inner.rotation as f64)
+        let r = r.unwrap().to_f32();
+        Float::new(_itself.modify(|t| {
+            t.rotation = r;
+            t.rotation
+        }) as f64)
     }
 
     fn set_scale(s: VectorData) -> VectorData {
-        let mut transform = _itself.get_data_mut(&*TRANSFORM_WRAPPER);
-        transform.inner.scale = s.unwrap().into();
-        From::from(transform.inner.scale)
+        let s = s.unwrap().into();
+        From::from(_itself.modify(|t| {
+            t.scale = s;
+            t.scale
+        }))
     }
 
     fn move_transform(v: VectorData) -> VectorData {
-        let mut transform = _itself.get_data_mut(&*TRANSFORM_WRAPPER);
-        transform.inner.position += Into::<Vector2<f32>>::into(v.unwrap());
-        From::from(transform.inner.position)
+        let v = Into::<Vector2<f32>>::into(v.unwrap());
+        From::from(_itself.modify(|t| {
+            t.position += v;
+            t.position
+        }))
     }
 
     fn move_toward(v: VectorData, max_delta: AnyNumber) -> VectorData {
         let max_delta = max_delta.unwrap().to_f32();
         let destination = Into::<Vector2<f32>>::into(v.unwrap());
-        let mut transform = _itself.get_data_mut(&*TRANSFORM_WRAPPER);
-        if (destination - transform.inner.position).magnitude() < max_delta {
-            transform.inner.position = destination;
-        } else {
-            transform.inner.position +=
-                max_delta * (destination - transform.inner.position).normalize();
-        }
-        From::from(transform.inner.position)
+        From::from(_itself.modify(|t| {
+            if (destination - t.position).magnitude() < max_delta {
+                t.position = destination;
+            } else {
+                t.position += max_delta * (destination - t.position).normalize();
+            }
+            t.position
+        }))
     }
 
     fn rotate_transform(r: AnyNumber) -> Float {
-        let mut transform = _itself.get_data_mut(&*TRANSFORM_WRAPPER);
-        transform.inner.rotation = (transform.inner.rotation + r.unwrap().to_f32()).normalize();
-        Float::new(transform.inner.rotation as f64)
+        let r = r.unwrap().to_f32();
+        Float::new(_itself.modify(|t| {
+            t.rotation = (t.rotation + r).normalize();
+            t.rotation
+        }) as f64)
     }
 
     fn rotate_toward(r: AnyNumber, max_delta: AnyNumber) -> Float {
         let r = r.unwrap().to_f32().normalize();
         let max_delta = max_delta.unwrap().to_f32();
-        let mut transform = _itself.get_data_mut(&*TRANSFORM_WRAPPER);
-        let (rotation_direction, rotation_distance) = rotation_state(transform.inner.rotation, r);
-        transform.inner.rotation = if rotation_distance <= max_delta {
-            r
-        } else {
-            (transform.inner.rotation + match rotation_direction {
-                RotationDirection::Clockwise => -max_delta,
-                RotationDirection::CounterClockwise => max_delta,
-            }).normalize()
-        };
-        Float::new(transform.inner.rotation as f64)
+        Float::new(_itself.modify(|t| {
+            let (rotation_direction, rotation_distance) = rotation_state(t.rotation, r);
+            t.rotation = if rotation_distance <= max_delta {
+                r
+            } else {
+                (t.rotation + match rotation_direction {
+                    RotationDirection::Clockwise => -max_delta,
+                    RotationDirection::CounterClockwise => max_delta,
+                }).normalize()
+            };
+            t.rotation
+        }) as f64)
     }
 );
 
@@ -0,0 +1,124 @@
+use super::game::GAME_WRAPPER;
+use super::game_object::GameObject;
+use super::transform::{Transform, TransformData};
+use nalgebra::Vector2;
+use rutie::{Module, Object, GC};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Transforms and velocities of game objects, kept in contiguous arrays so the
+/// engine can move every object without calling into Ruby. Scripts read and
+/// write an object's transform through a single `Transform` handle.
+#[derive(Default)]
+pub struct TransformStore {
+    slots: HashMap<u64, usize>,
+    ids: Vec<u64>,
+    handles: Vec<TransformData>,
+    transforms: Vec<Transform>,
+    velocities: Vec<Vector2<f32>>,
+    /// Whether the physics module moves the object instead of `integrate`
+    simulated: Vec<bool>,
+}
+
+impl TransformStore {
+    pub fn get(&self, id: u64) -> Option<&Transform> {
+        self.slots.get(&id).map(|&slot| &self.transforms[slot])
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut Transform> {
+        let transforms = &mut self.transforms;
+        self.slots.get(&id).map(move |&slot| &mut transforms[slot])
+    }
+
+    pub fn velocity(&self, id: u64) -> Option<Vector2<f32>> {
+        self.slots.get(&id).map(|&slot| self.velocities[slot])
+    }
+
+    pub fn handle(&self, id: u64) -> Option<TransformData> {
+        self.slots.get(&id).map(|&slot| self.handles[slot].clone())
+    }
+
+    /// The object's transform relative to its parent
+    pub fn local(&self, game_object: &GameObject) -> Transform {
+        game_object
+            .id()
+            .and_then(|id| self.get(id))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_transform(&mut self, id: u64, transform: Transform) -> TransformData {
+        let slot = self.slot(id);
+        self.transforms[slot] = transform;
+        self.handles[slot].clone()
+    }
+
+    pub fn set_velocity(&mut self, id: u64, velocity: Vector2<f32>) {
+        let slot = self.slot(id);
+        self.velocities[slot] = velocity;
+    }
+
+    pub fn set_simulated(&mut self, id: u64, simulated: bool) {
+        let slot = self.slot(id);
+        self.simulated[slot] = simulated;
+    }
+
+    /// Forgets the object. Its handle keeps the last transform it had.
+    pub fn remove(&mut self, id: u64) {
+        if let Some(slot) = self.slots.remove(&id) {
+            self.ids.swap_remove(slot);
+            self.velocities.swap_remove(slot);
+            self.simulated.swap_remove(slot);
+            let transform = self.transforms.swap_remove(slot);
+            self.handles.swap_remove(slot).detach(transform);
+            if let Some(&moved) = self.ids.get(slot) {
+                self.slots.insert(moved, slot);
+            }
+        }
+    }
+
+    /// Moves each object by its velocity over its own delta time. Objects
+    /// without a delta, e.g. those in a paused scene, stay where they are, and
+    /// simulated objects are left to the physics step.
+    pub fn integrate(&mut self, deltas: &HashMap<u64, Duration>) {
+        let deltas = self
+            .ids
+            .iter()
+            .zip(&self.simulated)
+            .map(|(id, &simulated)| match deltas.get(id) {
+                Some(delta) if !simulated => delta.as_secs_f32(),
+                _ => 0.0,
+            });
+        self.transforms
+            .iter_mut()
+            .zip(&self.velocities)
+            .zip(deltas)
+            .for_each(|((transform, velocity), delta)| transform.position += velocity * delta);
+    }
+
+    pub fn mark(&self) { self.handles.iter().for_each(GC::mark); }
+
+    /// Finds the object's slot, giving it an identity transform and no
+    /// velocity if it has none yet
+    fn slot(&mut self, id: u64) -> usize {
+        if let Some(&slot) = self.slots.get(&id) {
+            return slot;
+        }
+        let slot = self.ids.len();
+        self.slots.insert(id, slot);
+        self.ids.push(id);
+        self.handles.push(TransformData::handle(id));
+        self.transforms.push(Transform::default());
+        self.velocities.push(Vector2::zeros());
+        self.simulated.push(false);
+        slot
+    }
+}
+
+pub fn with_transforms<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut TransformStore) -> R,
+{
+    let mut inner = Module::from_existing("Game").instance_variable_get("@inner");
+    f(&mut inner.get_data_mut(&*GAME_WRAPPER).transforms)
+}
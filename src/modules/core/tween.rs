@@ -0,0 +1,454 @@
+use super::color::ColorData;
+use super::coroutines::{Coroutine, CoroutineHandle, Coroutines};
+use super::easing::Ease;
+use super::transform::{Transform, TransformData};
+use super::vector::VectorData;
+use crate::ext::{AnyNumber, HashExt};
+use nalgebra::Vector2;
+use rutie::{
+    AnyException, AnyObject, Array, Boolean, Class, Exception, Float, Hash, Integer, NilClass,
+    Object, RString, Symbol, VerifiedObject, GC, VM,
+};
+use std::time::Duration;
+
+const DEFAULT_DURATION: f32 = 1.0;
+
+wrappable_struct!(
+    TweenNode,
+    TweenWrapper,
+    TWEEN_WRAPPER,
+    mark(data) {
+        data.mark();
+    }
+);
+
+class!(Tween);
+
+/// A value that a tween can interpolate
+#[derive(Clone, Copy, Debug)]
+pub enum TweenValue {
+    Float(f32),
+    Vector(Vector2<f32>),
+    Color([f32; 4]),
+    Transform(Transform),
+}
+
+/// A description of a tween. Playing it copies the description, so the same
+/// `Tween` can be played any number of times.
+#[derive(Clone)]
+pub struct TweenNode {
+    kind: TweenKind,
+    on_complete: Option<AnyObject>,
+    completed: bool,
+    /// The time last sampled, relative to the start of this node
+    last: f32,
+}
+
+#[derive(Clone)]
+enum TweenKind {
+    Property(Property),
+    Sequence(Vec<TweenNode>),
+    Parallel(Vec<TweenNode>),
+    /// Plays the tween `loops` times, or forever without a limit. With `yoyo`
+    /// each loop plays forwards and then backwards.
+    Repeat {
+        tween: Box<TweenNode>,
+        loops: Option<u32>,
+        yoyo: bool,
+    },
+}
+
+/// Moves `target.attribute` towards `to`. Without an explicit `from`, the
+/// starting value is read when the property is first sampled.
+#[derive(Clone)]
+struct Property {
+    target: AnyObject,
+    attribute: String,
+    from: Option<TweenValue>,
+    to: TweenValue,
+    duration: f32,
+    ease: Ease,
+}
+
+/// A tween being played as the body of a coroutine
+pub struct Playback {
+    tween: TweenNode,
+    elapsed: f32,
+}
+
+impl TweenValue {
+    pub fn from_object(object: &AnyObject) -> Option<TweenValue> {
+        if let Ok(number) = object.try_convert_to::<AnyNumber>() {
+            Some(TweenValue::Float(number.to_f32()))
+        } else if let Ok(vector) = object.try_convert_to::<VectorData>() {
+            Some(TweenValue::Vector(vector.into()))
+        } else if let Ok(color) = object.try_convert_to::<ColorData>() {
+            Some(TweenValue::Color(color.into()))
+        } else if let Ok(transform) = object.try_convert_to::<TransformData>() {
+            Some(TweenValue::Transform(transform.get()))
+        } else {
+            None
+        }
+    }
+
+    pub fn to_object(self) -> AnyObject {
+        match self {
+            TweenValue::Float(value) => Float::new(value as f64).to_any_object(),
+            TweenValue::Vector(vector) => VectorData::from(vector).to_any_object(),
+            TweenValue::Color(color) => ColorData::from(color).to_any_object(),
+            TweenValue::Transform(transform) => TransformData::from(transform).to_any_object(),
+        }
+    }
+
+    /// The value `t` of the way from this one to `to`, or `None` when the two
+    /// are of different kinds. Rotations are interpolated without wrapping,
+    /// so tweening from 0 to 4π turns twice.
+    pub fn lerp(&self, to: &TweenValue, t: f32) -> Option<TweenValue> {
+        Some(match (self, to) {
+            (TweenValue::Float(a), TweenValue::Float(b)) => TweenValue::Float(a + (b - a) * t),
+            (TweenValue::Vector(a), TweenValue::Vector(b)) => TweenValue::Vector(a + (b - a) * t),
+            (TweenValue::Color(a), TweenValue::Color(b)) => {
+                let mut color = *a;
+                color
+                    .iter_mut()
+                    .zip(b.iter())
+                    .for_each(|(a, b)| *a += (b - *a) * t);
+                TweenValue::Color(color)
+            }
+            (TweenValue::Transform(a), TweenValue::Transform(b)) => {
+                TweenValue::Transform(Transform {
+                    position: a.position + (b.position - a.position) * t,
+                    rotation: a.rotation + (b.rotation - a.rotation) * t,
+                    scale: a.scale + (b.scale - a.scale) * t,
+                })
+            }
+            _ => return None,
+        })
+    }
+}
+
+impl TweenNode {
+    fn new(kind: TweenKind) -> Self {
+        TweenNode {
+            kind,
+            on_complete: None,
+            completed: false,
+            last: 0.0,
+        }
+    }
+
+    /// Length in seconds, which is infinite for tweens that loop forever
+    pub fn duration(&self) -> f32 {
+        match self.kind {
+            TweenKind::Property(ref property) => property.duration,
+            TweenKind::Sequence(ref tweens) => tweens.iter().map(TweenNode::duration).sum(),
+            TweenKind::Parallel(ref tweens) => {
+                tweens.iter().map(TweenNode::duration).fold(0.0, f32::max)
+            }
+            TweenKind::Repeat {
+                ref tween,
+                loops,
+                yoyo,
+            } => {
+                let cycle = tween.duration() * if yoyo { 2.0 } else { 1.0 };
+                match loops {
+                    Some(loops) => cycle * loops as f32,
+                    None if cycle > 0.0 => std::f32::INFINITY,
+                    None => 0.0,
+                }
+            }
+        }
+    }
+
+    /// Sets everything the tween animates to its value `time` seconds in.
+    /// Nested tweens are only sampled while they are in progress or when the
+    /// time since the last sample passed over them, so each one settles on
+    /// its final value and then leaves its target alone.
+    fn sample(&mut self, time: f32) -> Result<(), AnyException> {
+        let duration = self.duration();
+        let (from, to) = if time < self.last {
+            (time, self.last)
+        } else {
+            (self.last, time)
+        };
+        let last = self.last;
+        match self.kind {
+            TweenKind::Property(ref mut property) => property.sample(time)?,
+            TweenKind::Sequence(ref mut tweens) => {
+                let mut start = 0.0;
+                for tween in tweens {
+                    let length = tween.duration();
+                    if to >= start && from <= start + length {
+                        tween.sample((time - start).max(0.0).min(length))?;
+                    }
+                    start += length;
+                }
+            }
+            TweenKind::Parallel(ref mut tweens) => {
+                for tween in tweens {
+                    let length = tween.duration();
+                    if from <= length {
+                        tween.sample(time.min(length))?;
+                    }
+                }
+            }
+            TweenKind::Repeat {
+                ref mut tween,
+                yoyo,
+                ..
+            } => {
+                let length = tween.duration();
+                let cycle = if yoyo { 2.0 * length } else { length };
+                let lap = |t: f32| if cycle > 0.0 { (t / cycle).floor() } else { 0.0 };
+                if time < duration && lap(time) > lap(last) {
+                    // Finish the lap that just ended before starting the next
+                    tween.sample(if yoyo { 0.0 } else { length })?;
+                    tween.last = 0.0;
+                }
+                let position = if time >= duration {
+                    cycle
+                } else if cycle > 0.0 {
+                    time % cycle
+                } else {
+                    0.0
+                };
+                let local = if position > length { cycle - position } else { position };
+                tween.sample(local)?;
+            }
+        }
+        self.last = time;
+
+        let done = time >= duration;
+        if done && !self.completed {
+            self.completed = true;
+            if let Some(ref callback) = self.on_complete {
+                callback.protect_send("call", &[])?;
+            }
+        } else if !done {
+            self.completed = false;
+        }
+        Ok(())
+    }
+
+    fn mark(&self) {
+        self.on_complete.iter().for_each(GC::mark);
+        match self.kind {
+            TweenKind::Property(ref property) => GC::mark(&property.target),
+            TweenKind::Sequence(ref tweens) | TweenKind::Parallel(ref tweens) => {
+                tweens.iter().for_each(TweenNode::mark)
+            }
+            TweenKind::Repeat { ref tween, .. } => tween.mark(),
+        }
+    }
+}
+
+impl Property {
+    fn sample(&mut self, time: f32) -> Result<(), AnyException> {
+        let from = match self.from {
+            Some(from) => from,
+            None => {
+                let current = self.target.protect_send(&self.attribute, &[])?;
+                let from = TweenValue::from_object(&current)
+                    .filter(|from| from.lerp(&self.to, 0.0).is_some())
+                    .ok_or_else(|| {
+                        let current = inspect(&current);
+                        let message = format!("Can't tween {} from {}", self.attribute, current);
+                        AnyException::new("ArgumentError", Some(&message))
+                    })?;
+                self.from = Some(from);
+                from
+            }
+        };
+        let progress = if self.duration > 0.0 {
+            (time / self.duration).max(0.0).min(1.0)
+        } else {
+            1.0
+        };
+        if let Some(value) = from.lerp(&self.to, self.ease.apply(progress)) {
+            let setter = format!("{}=", self.attribute);
+            self.target.protect_send(&setter, &[value.to_object()])?;
+        }
+        Ok(())
+    }
+}
+
+impl Playback {
+    pub fn new(tween: TweenNode) -> Self { Playback { tween, elapsed: 0.0 } }
+
+    pub fn advance(&mut self, elapsed: Duration) { self.elapsed += elapsed.as_secs_f32(); }
+
+    /// Samples the tween at the time played so far, and returns whether it
+    /// has finished.
+    pub fn play(&mut self) -> Result<bool, AnyException> {
+        let duration = self.tween.duration();
+        self.tween.sample(self.elapsed.min(duration))?;
+        Ok(self.elapsed >= duration)
+    }
+
+    pub fn remaining(&self) -> f32 { (self.tween.duration() - self.elapsed).max(0.0) }
+
+    pub fn mark(&self) { self.tween.mark(); }
+}
+
+impl Tween {
+    fn new(tween: TweenNode) -> Self {
+        Class::from_existing("Tween").wrap_data(tween, &*TWEEN_WRAPPER)
+    }
+}
+
+/// Plays the tween as a coroutine, returning its handle
+pub fn play(coroutines: &mut Coroutines, tween: &Tween) -> CoroutineHandle {
+    let playback = Playback::new(tween.get_data(&*TWEEN_WRAPPER).clone());
+    let coroutine = Coroutine::tween(playback);
+    let handle = coroutine.handle.clone();
+    coroutines.push(coroutine);
+    handle
+}
+
+/// Builds the tween for `Tween.to` and the `tween` shorthands. Fails when
+/// `to:` is missing, or when it or `from:` can't be tweened.
+pub fn build(target: AnyObject, attribute: Symbol, options: Option<Hash>) -> Result<Tween, String> {
+    let attribute = attribute.to_str();
+    let options = options.ok_or_else(|| format!("Tween of {} needs a to: value", attribute))?;
+    let to = options.at(&Symbol::new("to"));
+    if to.is_nil() {
+        return Err(format!("Tween of {} needs a to: value", attribute));
+    }
+    let to = TweenValue::from_object(&to)
+        .ok_or_else(|| format!("Can't tween {} to {}", attribute, inspect(&to)))?;
+    let from = options.at(&Symbol::new("from"));
+    let from = if from.is_nil() {
+        None
+    } else {
+        let value = TweenValue::from_object(&from).filter(|from| from.lerp(&to, 0.0).is_some());
+        Some(value.ok_or_else(|| format!("Can't tween {} from {}", attribute, inspect(&from)))?)
+    };
+    let property = Property {
+        target,
+        attribute: attribute.to_owned(),
+        from,
+        to,
+        duration: options.get_num("duration").unwrap_or(DEFAULT_DURATION).max(0.0),
+        ease: options
+            .get_as::<Symbol>("ease")
+            .map_or(Ease::Linear, |name| ease_named(name.to_str())),
+    };
+    let tween = TweenNode::new(TweenKind::Property(property));
+    Ok(Tween::new(with_options(tween, Some(&options))))
+}
+
+fn inspect(object: &AnyObject) -> String {
+    object
+        .protect_send("inspect", &[])
+        .ok()
+        .and_then(|inspected| inspected.try_convert_to::<RString>().ok())
+        .map_or_else(|| "<uninspectable>".to_owned(), |inspected| inspected.to_string())
+}
+
+fn ease_named(name: &str) -> Ease {
+    Ease::from_name(name).unwrap_or_else(|| {
+        warn!("Unknown ease {}, tweening linearly instead", name);
+        Ease::Linear
+    })
+}
+
+/// Applies the `loop:`, `yoyo:` and `on_complete:` options. A block given to
+/// the calling method is taken as the completion callback.
+fn with_options(mut tween: TweenNode, options: Option<&Hash>) -> TweenNode {
+    if let Some(options) = options {
+        let yoyo = options
+            .get_as::<Boolean>("yoyo")
+            .map_or(false, |b| b.to_bool());
+        let looping = options.at(&Symbol::new("loop"));
+        let loops = if let Ok(loops) = looping.try_convert_to::<Integer>() {
+            Some(Some(loops.to_i64().max(0) as u32))
+        } else if looping.try_convert_to::<Boolean>().map_or(false, |b| b.to_bool()) {
+            Some(None)
+        } else if yoyo {
+            Some(Some(1))
+        } else {
+            None
+        };
+        if let Some(loops) = loops {
+            tween = TweenNode::new(TweenKind::Repeat {
+                tween: Box::new(tween),
+                loops,
+                yoyo,
+            });
+        }
+        let on_complete = options.at(&Symbol::new("on_complete"));
+        if !on_complete.is_nil() {
+            tween.on_complete = Some(on_complete);
+        }
+    }
+    if VM::is_block_given() {
+        tween.on_complete = Some(VM::block_proc().to_any_object());
+    }
+    tween
+}
+
+/// The tweens to play in a sequence or in parallel, failing on anything else
+fn tweens_in(method: &str, tweens: Array) -> Result<Vec<TweenNode>, String> {
+    tweens
+        .into_iter()
+        .map(|tween| match tween.try_convert_to::<Tween>() {
+            Ok(tween) => Ok(tween.get_data(&*TWEEN_WRAPPER).clone()),
+            Err(_) => Err(format!("Tween.{} expects Tweens, got {}", method, inspect(&tween))),
+        })
+        .collect()
+}
+
+/// Raises an `ArgumentError` with the message if building the tween failed
+fn tween_or_raise(tween: Result<Tween, String>) -> AnyObject {
+    match tween {
+        Ok(tween) => tween.to_any_object(),
+        Err(message) => {
+            VM::raise(Class::from_existing("ArgumentError"), &message);
+            NilClass::new().to_any_object()
+        }
+    }
+}
+
+#[rustfmt::skip]
+methods!(
+    Tween,
+    _itself,
+
+    fn tween_to(target: AnyObject, attribute: Symbol, options: Hash) -> AnyObject {
+        tween_or_raise(build(target.unwrap(), attribute.unwrap(), options.ok()))
+    }
+
+    fn tween_sequence(tweens: Array, options: Hash) -> AnyObject {
+        tween_or_raise(tweens_in("sequence", tweens.unwrap()).map(|tweens| {
+            let tween = TweenNode::new(TweenKind::Sequence(tweens));
+            Tween::new(with_options(tween, options.ok().as_ref()))
+        }))
+    }
+
+    fn tween_parallel(tweens: Array, options: Hash) -> AnyObject {
+        tween_or_raise(tweens_in("parallel", tweens.unwrap()).map(|tweens| {
+            let tween = TweenNode::new(TweenKind::Parallel(tweens));
+            Tween::new(with_options(tween, options.ok().as_ref()))
+        }))
+    }
+
+    fn get_duration() -> Float {
+        Float::new(_itself.get_data(&*TWEEN_WRAPPER).duration() as f64)
+    }
+);
+
+impl VerifiedObject for Tween {
+    fn is_correct_type<T: Object>(object: &T) -> bool {
+        object.class() == Class::from_existing("Tween")
+    }
+
+    fn error_message() -> &'static str { "Object is not of type Tween" }
+}
+
+pub fn add_tween_class() {
+    let mut class = Class::new("Tween", None);
+    class.def_self("to", tween_to);
+    class.def_self("sequence", tween_sequence);
+    class.def_self("parallel", tween_parallel);
+    class.def("duration", get_duration);
+}
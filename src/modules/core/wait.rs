@@ -1,19 +1,46 @@
+use super::coroutines::CoroutineHandle;
+use super::game::GAME_WRAPPER;
 use crate::ext::AnyNumber;
-use rutie::{AnyObject, Class, Integer, NilClass, Object, RString, VerifiedObject};
-
-wrappable_struct!(WaitInner, WaitWrapper, WAIT_WRAPPER);
+use rutie::{
+    AnyObject, Class, Integer, Module, NilClass, Object, RString, Symbol, VerifiedObject, GC, VM,
+};
+
+wrappable_struct!(
+    WaitInner,
+    WaitWrapper,
+    WAIT_WRAPPER,
+    mark(data) {
+        data.mark();
+    }
+);
 
 class!(Wait);
 
 class!(Waiter);
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum WaitInner {
     Seconds(f32),
     Frames(usize),
+    Until(AnyObject),
+    While(AnyObject),
+    Coroutine(CoroutineHandle),
+    /// The event, and how many times it had been emitted when the wait began
+    Event(String, u64),
+    Key(String),
     Done,
 }
 
+impl WaitInner {
+    pub fn mark(&self) {
+        match self {
+            WaitInner::Until(condition) | WaitInner::While(condition) => GC::mark(condition),
+            WaitInner::Coroutine(handle) => GC::mark(handle),
+            _ => {}
+        }
+    }
+}
+
 #[rustfmt::skip]
 methods!(
     Wait,
@@ -31,7 +58,7 @@ impl From<WaitInner> for Wait {
 }
 
 impl Into<WaitInner> for Wait {
-    fn into(self) -> WaitInner { *self.get_data(&*WAIT_WRAPPER) }
+    fn into(self) -> WaitInner { self.get_data(&*WAIT_WRAPPER).clone() }
 }
 
 impl VerifiedObject for Wait {
@@ -47,59 +74,89 @@ methods!(
     Waiter,
     _itself,
 
-    fn waiter_initialize(yielder: AnyObject) -> NilClass {
-        _itself.instance_variable_set("@yielder", yielder.unwrap());
-        NilClass::new()
-    }
-
     fn wait_for_seconds(seconds: AnyNumber) -> NilClass {
-        unsafe {
-            _itself.instance_variable_get("@yielder").send("yield", &[
-                Class::from_existing("Wait").wrap_data(
-                    WaitInner::Seconds(seconds.unwrap().to_f32()),
-                    &*WAIT_WRAPPER,
-                ),
-            ]);
-        }
-        NilClass::new()
+        _itself.suspend(WaitInner::Seconds(seconds.unwrap().to_f32()))
     }
 
     fn wait_for_frames(frames: Integer) -> NilClass {
-        unsafe {
-            _itself.instance_variable_get("@yielder").send("yield", &[
-                Class::from_existing("Wait").wrap_data(
-                    WaitInner::Frames(frames.unwrap().to_i32() as usize),
-                    &*WAIT_WRAPPER,
-                ),
-            ]);
-        }
-        NilClass::new()
+        _itself.suspend(WaitInner::Frames(frames.unwrap().to_i32() as usize))
     }
 
     fn wait_next_frame() -> NilClass {
-        unsafe {
-            _itself.instance_variable_get("@yielder").send("yield", &[
-                Class::from_existing("Wait").wrap_data(
-                    WaitInner::Frames(1),
-                    &*WAIT_WRAPPER,
-                ),
-            ]);
+        _itself.suspend(WaitInner::Frames(1))
+    }
+
+    fn wait_until() -> NilClass {
+        if VM::is_block_given() {
+            _itself.suspend(WaitInner::Until(VM::block_proc().to_any_object()))
+        } else {
+            NilClass::new()
         }
-        NilClass::new()
+    }
+
+    fn wait_while() -> NilClass {
+        if VM::is_block_given() {
+            _itself.suspend(WaitInner::While(VM::block_proc().to_any_object()))
+        } else {
+            NilClass::new()
+        }
+    }
+
+    fn wait_for_coroutine(handle: CoroutineHandle) -> NilClass {
+        _itself.suspend(WaitInner::Coroutine(handle.unwrap()))
+    }
+
+    fn wait_for_event(event: Symbol) -> NilClass {
+        let event = event.unwrap().to_str().to_owned();
+        let seen = Module::from_existing("Game")
+            .instance_variable_get("@inner")
+            .get_data(&*GAME_WRAPPER)
+            .events
+            .emitted(&event);
+        _itself.suspend(WaitInner::Event(event, seen))
+    }
+
+    fn wait_for_key(key: Symbol) -> NilClass {
+        _itself.suspend(WaitInner::Key(key.unwrap().to_str().to_owned()))
     }
 
     fn wait_done() -> NilClass {
+        _itself.suspend(WaitInner::Done)
+    }
+);
+
+impl Waiter {
+    /// Yields the wait from the coroutine's fiber to `Coroutines::execute`,
+    /// returning once the wait is over
+    fn suspend(&self, wait: WaitInner) -> NilClass {
         unsafe {
-            _itself.instance_variable_get("@yielder").send("yield", &[
-                Class::from_existing("Wait").wrap_data(
-                    WaitInner::Done,
-                    &*WAIT_WRAPPER,
-                ),
+            Class::from_existing("Fiber").send("yield", &[
+                Class::from_existing("Wait").wrap_data(wait, &*WAIT_WRAPPER),
             ]);
         }
         NilClass::new()
     }
-);
+}
+
+impl VerifiedObject for Waiter {
+    fn is_correct_type<T: Object>(object: &T) -> bool {
+        object.class() == Class::from_existing("Waiter")
+    }
+
+    fn error_message() -> &'static str { "Object is not of expected type Waiter" }
+}
+
+/// Suspends the coroutine that owns the waiter, if it is one. Returns whether
+/// it was.
+pub fn yield_to(waiter: &AnyObject, wait: WaitInner) -> bool {
+    match waiter.try_convert_to::<Waiter>() {
+        Ok(waiter) => {
+            waiter.suspend(wait);
+            true
+        }
+        Err(_) => false,
+    }
+}
 
 fn add_wait_class() {
     let mut class = Class::new("Wait", None);
@@ -108,11 +165,15 @@ fn add_wait_class() {
 
 fn add_waiter_class() {
     let mut class = Class::new("Waiter", None);
-    class.def("initialize", waiter_initialize);
 
     class.def("for_seconds", wait_for_seconds);
     class.def("for_frames", wait_for_frames);
     class.def("next_frame", wait_next_frame);
+    class.def("until", wait_until);
+    class.def("while", wait_while);
+    class.def("for_coroutine", wait_for_coroutine);
+    class.def("for_event", wait_for_event);
+    class.def("for_key", wait_for_key);
     class.def("done", wait_done);
 }
 
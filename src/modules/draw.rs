@@ -1,10 +1,11 @@
 use self::font_loader::FontLoader;
+pub use self::headless::HeadlessDrawModule;
 use self::primitive::{PrimitiveProgram, PrimitiveShaderInterface};
 use self::sprite::{SpriteData, SpriteProgram, SpriteProgramBase, SpriteShaderInterface};
 use self::spritesheet_loader::SpritesheetLoader;
 use self::text::{TextProgram, TextProgramBase};
-use super::core::INPUT_WRAPPER;
-use super::{EngineModule, GameState};
+use super::core::{CoreModule, INPUT_WRAPPER};
+use super::{EngineModule, GameState, DRAW_MODULE};
 use glyph_brush::{HorizontalAlign, VerticalAlign};
 use luminance::context::GraphicsContext;
 use luminance::framebuffer::Framebuffer;
@@ -22,6 +23,7 @@ use std::path::PathBuf;
 use std::sync::mpsc::{Receiver, Sender};
 
 mod font_loader;
+mod headless;
 mod primitive;
 mod ruby;
 mod sprite;
@@ -359,23 +361,9 @@ impl<'a, G> EngineModule<G> for DrawModule<'a>
 where
     G: GameState,
 {
-    fn init(&mut self, _: &mut G) {
-        let mut module = Module::new("Draw");
-        module.define_nested_class("DrawQueue", None);
-        module.instance_variable_set("@queue", self::ruby::DrawQueue::new());
-
-        module.def_self("load_font", self::ruby::load_font);
-        module.def_self("load_spritesheet", self::ruby::load_spritesheet);
-        module.def_self("create_sprite", self::ruby::create_sprite);
-
-        module.def_self("arc!", self::ruby::draw_arc);
-        module.def_self("circle!", self::ruby::draw_circle);
-        module.def_self("line!", self::ruby::draw_line);
-        module.def_self("rect!", self::ruby::draw_rectangle);
-        module.def_self("rectangle!", self::ruby::draw_rectangle);
-        module.def_self("sprite!", self::ruby::draw_sprite);
-        module.def_self("text!", self::ruby::draw_text);
-    }
+    fn name(&self) -> &'static str { DRAW_MODULE }
+
+    fn init(&mut self, _: &mut G) { self::ruby::add_draw_module(); }
 
     fn pre_update(&mut self, _: &mut G) {
         self.clear_commands();
@@ -383,9 +371,7 @@ where
         let mut key_events = Vec::new();
         for event in self.surface.poll_events() {
             match event {
-                WindowEvent::Close => {
-                    panic!("Interrupt requested");
-                }
+                WindowEvent::Close => CoreModule::request_quit(),
                 WindowEvent::Key(key, _, action, _) => {
                     key_events.push((key, action));
                 }
@@ -397,7 +383,7 @@ where
         }
     }
 
-    fn post_update(&mut self, game_state: &mut G) {
+    fn render(&mut self, game_state: &mut G) {
         self.handle_font_loading();
         self.handle_spritesheet_loading();
         self.prepare_render(game_state);
@@ -0,0 +1,35 @@
+use super::ruby::DrawQueue;
+use super::{DrawCommand, SpritesheetLoadRequest, SpritesheetSlice};
+use crate::modules::{EngineModule, GameState, DRAW_MODULE};
+use rutie::{Module, Object};
+use std::path::PathBuf;
+
+/// Stands in for `DrawModule` when there is no window. The `Draw` Ruby module
+/// behaves the same, but queued commands and load requests are discarded at
+/// the start of the following frame.
+pub struct HeadlessDrawModule;
+
+impl HeadlessDrawModule {
+    fn discard_queue(&mut self) {
+        let queue = Module::from_existing("Draw")
+            .instance_variable_get("@queue")
+            .try_convert_to::<DrawQueue>();
+        if let Ok(mut queue) = queue {
+            AsMut::<Vec<DrawCommand>>::as_mut(&mut queue).clear();
+            AsMut::<Vec<(String, PathBuf)>>::as_mut(&mut queue).clear();
+            AsMut::<Vec<SpritesheetLoadRequest>>::as_mut(&mut queue).clear();
+            AsMut::<Vec<SpritesheetSlice>>::as_mut(&mut queue).clear();
+        }
+    }
+}
+
+impl<G> EngineModule<G> for HeadlessDrawModule
+where
+    G: GameState,
+{
+    fn name(&self) -> &'static str { DRAW_MODULE }
+
+    fn init(&mut self, _: &mut G) { super::ruby::add_draw_module(); }
+
+    fn pre_update(&mut self, _: &mut G) { self.discard_queue(); }
+}
@@ -236,6 +236,24 @@ methods!(
     }
 );
 
+pub fn add_draw_module() {
+    let mut module = Module::new("Draw");
+    module.define_nested_class("DrawQueue", None);
+    module.instance_variable_set("@queue", DrawQueue::new());
+
+    module.def_self("load_font", load_font);
+    module.def_self("load_spritesheet", load_spritesheet);
+    module.def_self("create_sprite", create_sprite);
+
+    module.def_self("arc!", draw_arc);
+    module.def_self("circle!", draw_circle);
+    module.def_self("line!", draw_line);
+    module.def_self("rect!", draw_rectangle);
+    module.def_self("rectangle!", draw_rectangle);
+    module.def_self("sprite!", draw_sprite);
+    module.def_self("text!", draw_text);
+}
+
 impl DrawQueue {
     pub fn new() -> Self {
         Module::from_existing("Draw")
@@ -0,0 +1,95 @@
+use super::core::{CoreModule, ScriptError, ScriptPhase};
+use super::{EngineModule, GameState};
+use rutie::{Array, Module, Object, RString, VM};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watches the entry script and every script it has required from the same
+/// directory tree, and loads them again when they change. Reloading reopens
+/// the classes in place, so live game objects and their coroutines are kept
+/// and pick up redefined methods on the next frame. Top-level code runs again
+/// too, and can check `Game.reloading?` to avoid creating objects twice.
+pub struct HotReloadModule {
+    root: PathBuf,
+    modified: HashMap<PathBuf, SystemTime>,
+    /// `None` until the first frame polls straight away, recording the times
+    /// of the scripts just after the entry script has loaded
+    last_poll: Option<Instant>,
+}
+
+impl HotReloadModule {
+    pub fn new(entry_script: &PathBuf) -> Self {
+        let root = entry_script
+            .canonicalize()
+            .ok()
+            .and_then(|p| p.parent().map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("."));
+        HotReloadModule {
+            root,
+            modified: HashMap::new(),
+            last_poll: None,
+        }
+    }
+
+    /// Scripts loaded by Ruby that belong to the game rather than a library
+    fn watched_scripts(&self) -> Vec<PathBuf> {
+        VM::eval("$LOADED_FEATURES")
+            .ok()
+            .and_then(|features| features.try_convert_to::<Array>().ok())
+            .map(|features| {
+                features
+                    .into_iter()
+                    .filter_map(|f| f.try_convert_to::<RString>().ok())
+                    .map(|f| PathBuf::from(f.to_string()))
+                    .filter(|f| f.starts_with(&self.root))
+                    .collect()
+            })
+            .unwrap_or_else(Vec::new)
+    }
+
+    fn changed_scripts(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for script in self.watched_scripts() {
+            let modified = match fs::metadata(&script).and_then(|m| m.modified()) {
+                Ok(modified) => modified,
+                Err(_) => continue,
+            };
+            if let Some(previous) = self.modified.insert(script.clone(), modified) {
+                if previous != modified {
+                    changed.push(script);
+                }
+            }
+        }
+        changed
+    }
+
+    fn reload(&self, script: &PathBuf) {
+        let path = RString::new_utf8(&script.to_string_lossy());
+        CoreModule::set_reloading(true);
+        let result = Module::from_existing("Kernel").protect_send("load", &[path.to_any_object()]);
+        CoreModule::set_reloading(false);
+        match result {
+            Ok(_) => info!("Reloaded {}", script.display()),
+            Err(e) => ScriptError::new(&e, ScriptPhase::Load).report(),
+        }
+    }
+}
+
+impl<G> EngineModule<G> for HotReloadModule
+where
+    G: GameState,
+{
+    fn pre_update(&mut self, _: &mut G) {
+        if self.last_poll.map_or(false, |last| last.elapsed() < POLL_INTERVAL) {
+            return;
+        }
+        self.last_poll = Some(Instant::now());
+        for script in self.changed_scripts() {
+            self.reload(&script);
+        }
+    }
+}
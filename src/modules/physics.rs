@@ -0,0 +1,214 @@
+pub use self::body::Body;
+pub use self::collider::Collider;
+pub use self::collision::CollisionModule;
+pub use self::shape::{Aabb, Contact, Shape};
+use self::body::BODY_WRAPPER;
+use super::core::{CoreModule, GameInner, GameObject, VectorData};
+use super::{EngineModule, GameState, COLLISION_MODULE, CORE_MODULE, PHYSICS_MODULE};
+use nalgebra::Vector2;
+use rutie::{AnyObject, Array, Class, Integer, Module, NilClass, Object, Symbol};
+use std::collections::HashSet;
+
+mod body;
+mod broadphase;
+mod collider;
+mod collision;
+mod contact;
+mod shape;
+
+/// Masks are 32 bits wide
+const MAX_LAYERS: usize = 32;
+
+wrappable_struct!(PhysicsInner, PhysicsWrapper, PHYSICS_WRAPPER);
+
+module!(Physics);
+
+pub struct PhysicsInner {
+    gravity: Vector2<f32>,
+    /// Layer names in the order their bits were given out
+    layers: Vec<String>,
+}
+
+/// Moves objects that have a `Body` in every fixed update, after their
+/// `fixed_update!`. Collisions between their colliders are left to the
+/// `CollisionModule`, which runs next.
+#[derive(Default)]
+pub struct PhysicsModule {
+    simulated: HashSet<u64>,
+}
+
+impl PhysicsInner {
+    fn layer_bit(&self, name: &str) -> Option<u32> {
+        self.layers.iter().position(|l| l == name).map(|i| 1 << i)
+    }
+}
+
+impl PhysicsModule {
+    pub fn gravity() -> Vector2<f32> {
+        Module::from_existing("Physics")
+            .instance_variable_get("@inner")
+            .get_data(&*PHYSICS_WRAPPER)
+            .gravity
+    }
+
+    pub fn set_gravity(gravity: Vector2<f32>) {
+        Module::from_existing("Physics")
+            .instance_variable_get("@inner")
+            .get_data_mut(&*PHYSICS_WRAPPER)
+            .gravity = gravity;
+    }
+
+    /// The bit of a collision layer, if a collider has used it
+    pub fn layer_bit(name: &str) -> Option<u32> {
+        Module::from_existing("Physics")
+            .instance_variable_get("@inner")
+            .get_data(&*PHYSICS_WRAPPER)
+            .layer_bit(name)
+    }
+
+    /// The bit of a collider's layer. Layers get the next free bit the first
+    /// time a collider is put on them; past the 32nd they get none and collide
+    /// with nothing.
+    pub fn register_layer(name: &str) -> u32 {
+        let mut inner = Module::from_existing("Physics").instance_variable_get("@inner");
+        let inner = inner.get_data_mut(&*PHYSICS_WRAPPER);
+        if let Some(bit) = inner.layer_bit(name) {
+            return bit;
+        }
+        if inner.layers.len() >= MAX_LAYERS {
+            warn!("Collision layer {} is past the {} layer limit", name, MAX_LAYERS);
+            return 0;
+        }
+        inner.layers.push(name.to_owned());
+        1 << (inner.layers.len() - 1)
+    }
+
+    /// Moves the bodies of the objects that update this step
+    fn step(&mut self) {
+        let gravity = Self::gravity();
+        CoreModule::inspect_mut(|game| {
+            let delta = game.time.fixed_delta.unwrap_or(game.time.delta).as_secs_f32();
+            let (game_objects, _) = stepped_objects(game);
+            self.integrate(game, &game_objects, gravity, delta);
+        });
+    }
+
+    /// Applies gravity, forces, impulses and drag to each body's velocity,
+    /// then moves it. Objects that have lost their body go back to being
+    /// moved by the core module, while paused ones keep theirs for later.
+    fn integrate(
+        &mut self,
+        game: &mut GameInner,
+        game_objects: &[GameObject],
+        gravity: Vector2<f32>,
+        delta: f32,
+    ) {
+        let transforms = &mut game.transforms;
+        let mut simulated = HashSet::new();
+        let mut held = Vec::new();
+        for game_object in game_objects {
+            let (id, mut body) = match (game_object.id(), game_object.body()) {
+                (Some(id), Some(body)) => (id, body),
+                _ => continue,
+            };
+            simulated.insert(id);
+            transforms.set_simulated(id, true);
+            let body = body.get_data_mut(&*BODY_WRAPPER);
+            let (force, impulse) = body.take_applied();
+            let inverse_mass = body.inverse_mass();
+            if inverse_mass <= 0.0 {
+                continue;
+            }
+            let velocity = transforms.velocity(id).unwrap_or_else(Vector2::zeros)
+                + (gravity * body.gravity_scale + force * inverse_mass) * delta
+                + impulse * inverse_mass;
+            let velocity = velocity / (1.0 + body.drag.max(0.0) * delta);
+            transforms.set_velocity(id, velocity);
+            if let Some(transform) = transforms.get_mut(id) {
+                transform.position += velocity * delta;
+            }
+        }
+        for &id in self.simulated.difference(&simulated) {
+            match game.index.get(id) {
+                Some(game_object) if game_object.body().is_some() => held.push(id),
+                _ if transforms.get(id).is_some() => transforms.set_simulated(id, false),
+                _ => {}
+            }
+        }
+        simulated.extend(held);
+        self.simulated = simulated;
+    }
+}
+
+/// Splits the live, switched on objects into those that update this step and
+/// the ids of those that are only paused
+fn stepped_objects(game: &GameInner) -> (Vec<GameObject>, HashSet<u64>) {
+    let (game_objects, held): (Vec<_>, Vec<_>) = game
+        .active_objects()
+        .cloned()
+        .partition(|o| game.should_update(o));
+    let held = held.iter().filter_map(GameObject::id).collect();
+    (game_objects, held)
+}
+
+#[rustfmt::skip]
+methods!(
+    Physics,
+    _itself,
+
+    fn get_gravity() -> VectorData {
+        PhysicsModule::gravity().into()
+    }
+
+    fn set_gravity(gravity: VectorData) -> VectorData {
+        PhysicsModule::set_gravity(gravity.unwrap().into());
+        PhysicsModule::gravity().into()
+    }
+
+    fn get_layers() -> Array {
+        let inner = _itself.instance_variable_get("@inner");
+        inner
+            .get_data(&*PHYSICS_WRAPPER)
+            .layers
+            .iter()
+            .map(|l| Symbol::new(l).to_any_object())
+            .collect()
+    }
+
+    fn layer_bit(layer: Symbol) -> AnyObject {
+        match PhysicsModule::layer_bit(layer.unwrap().to_str()) {
+            Some(bit) => Integer::new(bit.into()).to_any_object(),
+            None => NilClass::new().to_any_object(),
+        }
+    }
+);
+
+fn add_physics_module() {
+    let mut module = Module::new("Physics");
+    let inner: AnyObject = Class::from_existing("Object").wrap_data(
+        PhysicsInner {
+            gravity: Vector2::zeros(),
+            layers: vec![collider::DEFAULT_LAYER.to_owned()],
+        },
+        &*PHYSICS_WRAPPER,
+    );
+    module.instance_variable_set("@inner", inner);
+
+    module.def_self("gravity", get_gravity);
+    module.def_self("gravity=", set_gravity);
+    module.def_self("layers", get_layers);
+    module.def_self("layer_bit", layer_bit);
+}
+
+impl<G> EngineModule<G> for PhysicsModule
+where
+    G: GameState,
+{
+    fn name(&self) -> &'static str { PHYSICS_MODULE }
+
+    fn run_after(&self) -> Vec<&'static str> { vec![CORE_MODULE] }
+
+    fn run_before(&self) -> Vec<&'static str> { vec![COLLISION_MODULE] }
+
+    fn fixed_update(&mut self, _: &mut G) { self.step(); }
+}
@@ -0,0 +1,202 @@
+use crate::ext::{AnyNumber, HashExt};
+use crate::modules::core::{GameObject, VectorData};
+use nalgebra::Vector2;
+use rutie::{Boolean, Class, Float, Hash, NilClass, Object, VerifiedObject};
+
+pub const DEFAULT_FRICTION: f32 = 0.2;
+
+wrappable_struct!(BodyInner, BodyWrapper, BODY_WRAPPER);
+
+class!(Body);
+
+/// The physical properties of a game object. The physics step moves objects
+/// with a body by their velocity instead of the core module.
+pub struct BodyInner {
+    pub mass: f32,
+    /// Linear damping, slowing the body in proportion to its speed
+    pub drag: f32,
+    /// How much of the closing speed is kept after a collision, from 0 to 1
+    pub restitution: f32,
+    pub friction: f32,
+    pub gravity_scale: f32,
+    /// Static bodies never move, but others collide with them
+    pub fixed: bool,
+    force: Vector2<f32>,
+    impulse: Vector2<f32>,
+}
+
+impl BodyInner {
+    /// Zero for static bodies and those without mass, which nothing can move
+    pub fn inverse_mass(&self) -> f32 {
+        if self.fixed || self.mass <= 0.0 {
+            0.0
+        } else {
+            1.0 / self.mass
+        }
+    }
+
+    /// Takes the force and impulse applied since the last step
+    pub fn take_applied(&mut self) -> (Vector2<f32>, Vector2<f32>) {
+        let applied = (self.force, self.impulse);
+        self.force = Vector2::zeros();
+        self.impulse = Vector2::zeros();
+        applied
+    }
+}
+
+/// Non-finite masses make the body immovable, like a mass of zero
+fn clamp_mass(mass: f32) -> f32 {
+    if mass.is_finite() {
+        mass.max(0.0)
+    } else {
+        0.0
+    }
+}
+
+fn clamp_drag(drag: f32) -> f32 {
+    if drag.is_finite() {
+        drag.max(0.0)
+    } else {
+        0.0
+    }
+}
+
+fn clamp_restitution(restitution: f32) -> f32 { restitution.max(0.0).min(1.0) }
+
+fn clamp_friction(friction: f32) -> f32 { friction.max(0.0) }
+
+impl GameObject {
+    pub fn body(&self) -> Option<Body> {
+        self.instance_variable_get("@body")
+            .try_convert_to::<Body>()
+            .ok()
+    }
+}
+
+#[rustfmt::skip]
+methods!(
+    Body,
+    _itself,
+
+    fn new_body(options: Hash) -> Body {
+        let options = options.ok();
+        let option = |key: &str, default: f32| {
+            options.as_ref().and_then(|o| o.get_num(key)).unwrap_or(default)
+        };
+        let inner = BodyInner {
+            mass: clamp_mass(option("mass", 1.0)),
+            drag: clamp_drag(option("drag", 0.0)),
+            restitution: clamp_restitution(option("restitution", 0.0)),
+            friction: clamp_friction(option("friction", DEFAULT_FRICTION)),
+            gravity_scale: option("gravity_scale", 1.0),
+            fixed: options
+                .as_ref()
+                .and_then(|o| o.get_as::<Boolean>("static"))
+                .map_or(false, |b| b.to_bool()),
+            force: Vector2::zeros(),
+            impulse: Vector2::zeros(),
+        };
+        Class::from_existing("Body").wrap_data(inner, &*BODY_WRAPPER)
+    }
+
+    fn get_mass() -> Float {
+        Float::new(_itself.get_data(&*BODY_WRAPPER).mass as f64)
+    }
+
+    fn set_mass(mass: AnyNumber) -> Float {
+        let mass = clamp_mass(mass.unwrap().to_f32());
+        _itself.get_data_mut(&*BODY_WRAPPER).mass = mass;
+        Float::new(mass as f64)
+    }
+
+    fn get_drag() -> Float {
+        Float::new(_itself.get_data(&*BODY_WRAPPER).drag as f64)
+    }
+
+    fn set_drag(drag: AnyNumber) -> Float {
+        let drag = clamp_drag(drag.unwrap().to_f32());
+        _itself.get_data_mut(&*BODY_WRAPPER).drag = drag;
+        Float::new(drag as f64)
+    }
+
+    fn get_restitution() -> Float {
+        Float::new(_itself.get_data(&*BODY_WRAPPER).restitution as f64)
+    }
+
+    fn set_restitution(restitution: AnyNumber) -> Float {
+        let restitution = clamp_restitution(restitution.unwrap().to_f32());
+        _itself.get_data_mut(&*BODY_WRAPPER).restitution = restitution;
+        Float::new(restitution as f64)
+    }
+
+    fn get_friction() -> Float {
+        Float::new(_itself.get_data(&*BODY_WRAPPER).friction as f64)
+    }
+
+    fn set_friction(friction: AnyNumber) -> Float {
+        let friction = clamp_friction(friction.unwrap().to_f32());
+        _itself.get_data_mut(&*BODY_WRAPPER).friction = friction;
+        Float::new(friction as f64)
+    }
+
+    fn get_gravity_scale() -> Float {
+        Float::new(_itself.get_data(&*BODY_WRAPPER).gravity_scale as f64)
+    }
+
+    fn set_gravity_scale(scale: AnyNumber) -> Float {
+        let scale = scale.unwrap().to_f32();
+        _itself.get_data_mut(&*BODY_WRAPPER).gravity_scale = scale;
+        Float::new(scale as f64)
+    }
+
+    fn is_static() -> Boolean {
+        Boolean::new(_itself.get_data(&*BODY_WRAPPER).fixed)
+    }
+
+    fn set_static(fixed: Boolean) -> Boolean {
+        let fixed = fixed.map(|f| f.to_bool()).unwrap_or(false);
+        _itself.get_data_mut(&*BODY_WRAPPER).fixed = fixed;
+        Boolean::new(fixed)
+    }
+
+    fn apply_force(force: VectorData) -> NilClass {
+        let force: Vector2<f32> = force.unwrap().into();
+        _itself.get_data_mut(&*BODY_WRAPPER).force += force;
+        NilClass::new()
+    }
+
+    fn apply_impulse(impulse: VectorData) -> NilClass {
+        let impulse: Vector2<f32> = impulse.unwrap().into();
+        _itself.get_data_mut(&*BODY_WRAPPER).impulse += impulse;
+        NilClass::new()
+    }
+);
+
+impl VerifiedObject for Body {
+    fn is_correct_type<T: Object>(object: &T) -> bool {
+        object.class() == Class::from_existing("Body")
+    }
+
+    fn error_message() -> &'static str { "Object is not of type Body" }
+}
+
+pub fn add_body_class() {
+    let mut class = Class::new("Body", None);
+    class.def_self("new", new_body);
+
+    class.def("mass", get_mass);
+    class.def("mass=", set_mass);
+    class.def("drag", get_drag);
+    class.def("drag=", set_drag);
+    class.def("restitution", get_restitution);
+    class.def("restitution=", set_restitution);
+    class.def("friction", get_friction);
+    class.def("friction=", set_friction);
+    class.def("gravity_scale", get_gravity_scale);
+    class.def("gravity_scale=", set_gravity_scale);
+    class.def("static?", is_static);
+    class.def("static=", set_static);
+
+    class.def("apply_force", apply_force);
+    class.def("apply_impulse", apply_impulse);
+}
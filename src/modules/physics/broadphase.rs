@@ -0,0 +1,77 @@
+use super::shape::Aabb;
+use std::cmp::Ordering;
+
+/// Pairs of indices whose bounds overlap, found by sorting the bounds along
+/// the x axis and sweeping across them. Each pair is ordered, and the pairs
+/// are sorted, so that collisions are handled in the same order every step.
+pub fn overlapping_pairs(bounds: &[Aabb]) -> Vec<(usize, usize)> {
+    let mut order = (0..bounds.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| {
+        bounds[a]
+            .min
+            .x
+            .partial_cmp(&bounds[b].min.x)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let mut active: Vec<usize> = Vec::new();
+    let mut pairs = Vec::new();
+    for i in order {
+        active.retain(|&j| bounds[j].max.x >= bounds[i].min.x);
+        pairs.extend(
+            active
+                .iter()
+                .filter(|&&j| bounds[i].overlaps(&bounds[j]))
+                .map(|&j| (i.min(j), i.max(j))),
+        );
+        active.push(i);
+    }
+    pairs.sort_unstable();
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Vector2;
+
+    fn aabb(min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Aabb {
+        Aabb {
+            min: Vector2::new(min_x, min_y),
+            max: Vector2::new(max_x, max_y),
+        }
+    }
+
+    #[test]
+    fn finds_only_pairs_that_overlap_on_both_axes() {
+        let bounds = vec![
+            aabb(2.0, 0.0, 3.0, 1.0),
+            aabb(0.0, 0.0, 2.5, 1.0),
+            aabb(0.5, 5.0, 1.5, 6.0),
+            aabb(10.0, 0.0, 11.0, 1.0),
+            aabb(2.8, 0.8, 4.0, 2.0),
+        ];
+        assert_eq!(overlapping_pairs(&bounds), vec![(0, 1), (0, 4)]);
+    }
+
+    #[test]
+    fn bounds_that_touch_overlap() {
+        let bounds = vec![aabb(1.0, 0.0, 2.0, 1.0), aabb(0.0, 1.0, 1.0, 2.0)];
+        assert_eq!(overlapping_pairs(&bounds), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn pairs_are_ordered_and_sorted() {
+        let bounds = (0..4).rev().map(|i| aabb(i as f32 * 0.1, 0.0, 1.0, 1.0)).collect::<Vec<_>>();
+        assert_eq!(
+            overlapping_pairs(&bounds),
+            vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]
+        );
+    }
+
+    #[test]
+    fn no_bounds_no_pairs() {
+        assert!(overlapping_pairs(&[]).is_empty());
+        assert!(overlapping_pairs(&[aabb(0.0, 0.0, 1.0, 1.0)]).is_empty());
+    }
+}
@@ -0,0 +1,269 @@
+use super::shape::{convex_hull, Aabb, Shape};
+use super::{PhysicsInner, PhysicsModule};
+use crate::ext::{AnyNumber, HashExt};
+use crate::modules::core::{GameObject, Transform, VectorData};
+use nalgebra::Vector2;
+use rutie::{
+    AnyObject, Array, Boolean, Class, Hash, Integer, NilClass, Object, Symbol, VerifiedObject,
+};
+
+pub const DEFAULT_LAYER: &str = "default";
+
+wrappable_struct!(ColliderInner, ColliderWrapper, COLLIDER_WRAPPER);
+wrappable_struct!(MaskInner, MaskWrapper, MASK_WRAPPER);
+
+class!(Collider);
+
+pub struct ColliderInner {
+    shape: LocalShape,
+    layer: String,
+    /// The layer's bit, given out when the collider is put on the layer
+    bit: u32,
+    /// Triggers report overlaps without pushing anything apart
+    trigger: bool,
+}
+
+/// A `collision_mask` as assigned, with the bits of the layers it names.
+/// Layers that no collider had used yet get their bit once one does.
+pub struct MaskInner {
+    layers: Vec<String>,
+    bits: u32,
+    /// How many of the known layers have been looked up
+    resolved: usize,
+}
+
+/// A collider's shape relative to its object's transform
+enum LocalShape {
+    Circle {
+        offset: Vector2<f32>,
+        radius: f32,
+    },
+    Rect {
+        offset: Vector2<f32>,
+        size: Vector2<f32>,
+    },
+    Polygon(Vec<Vector2<f32>>),
+}
+
+impl Collider {
+    /// Places the shape with the object's world transform. Circles grow with
+    /// the larger of the two scales.
+    pub fn shape_at(&self, transform: &Transform) -> Shape {
+        let (sin, cos) = transform.rotation.sin_cos();
+        let scale = transform.scale;
+        let place = |p: &Vector2<f32>| {
+            let p = p.component_mul(&scale);
+            transform.position + Vector2::new(p.x * cos - p.y * sin, p.x * sin + p.y * cos)
+        };
+        // Mirroring along one axis reverses the winding of the points
+        let mirrored = scale.x * scale.y < 0.0;
+        match self.get_data(&*COLLIDER_WRAPPER).shape {
+            LocalShape::Circle { ref offset, radius } => Shape::Circle {
+                center: place(offset),
+                radius: radius * scale.x.abs().max(scale.y.abs()),
+            },
+            LocalShape::Rect { ref offset, ref size } if sin.abs() <= std::f32::EPSILON => {
+                let half = size.component_mul(&scale).abs() / 2.0;
+                let center = place(offset);
+                Shape::Aabb(Aabb {
+                    min: center - half,
+                    max: center + half,
+                })
+            }
+            LocalShape::Rect { ref offset, ref size } => {
+                let half = size / 2.0;
+                let mut points = vec![
+                    place(&(offset - half)),
+                    place(&(offset + Vector2::new(half.x, -half.y))),
+                    place(&(offset + half)),
+                    place(&(offset + Vector2::new(-half.x, half.y))),
+                ];
+                if mirrored {
+                    points.reverse();
+                }
+                Shape::Polygon(points)
+            }
+            LocalShape::Polygon(ref points) => {
+                let mut points = points.iter().map(place).collect::<Vec<_>>();
+                if mirrored {
+                    points.reverse();
+                }
+                Shape::Polygon(points)
+            }
+        }
+    }
+
+    /// The bit of the collider's layer
+    pub fn layer(&self) -> u32 { self.get_data(&*COLLIDER_WRAPPER).bit }
+
+    pub fn is_trigger(&self) -> bool { self.get_data(&*COLLIDER_WRAPPER).trigger }
+
+    /// Applies the `layer:` and `trigger:` options shared by every shape
+    fn new(shape: LocalShape, options: Option<Hash>) -> Self {
+        let layer = options
+            .as_ref()
+            .and_then(|o| o.get_as::<Symbol>("layer"))
+            .map_or_else(|| DEFAULT_LAYER.to_owned(), |l| l.to_str().to_owned());
+        let inner = ColliderInner {
+            shape,
+            bit: PhysicsModule::register_layer(&layer),
+            layer,
+            trigger: options
+                .as_ref()
+                .and_then(|o| o.get_as::<Boolean>("trigger"))
+                .map_or(false, |b| b.to_bool()),
+        };
+        Class::from_existing("Collider").wrap_data(inner, &*COLLIDER_WRAPPER)
+    }
+}
+
+impl GameObject {
+    pub fn collider(&self) -> Option<Collider> {
+        self.instance_variable_get("@collider")
+            .try_convert_to::<Collider>()
+            .ok()
+    }
+
+    /// The layers the object collides with, every one if it has no mask
+    pub fn collision_mask(&self, physics: &PhysicsInner) -> u32 {
+        let mut mask = self.instance_variable_get("@collision_bits");
+        if mask.is_nil() {
+            return std::u32::MAX;
+        }
+        let mask = mask.get_data_mut(&*MASK_WRAPPER);
+        for (i, layer) in physics.layers.iter().enumerate().skip(mask.resolved) {
+            if mask.layers.contains(layer) {
+                mask.bits |= 1 << i;
+            }
+        }
+        mask.resolved = physics.layers.len();
+        mask.bits
+    }
+}
+
+fn offset_option(options: &Option<Hash>) -> Vector2<f32> {
+    options
+        .as_ref()
+        .and_then(|o| o.get_as::<VectorData>("offset"))
+        .map_or_else(Vector2::zeros, Into::into)
+}
+
+#[rustfmt::skip]
+methods!(
+    Collider,
+    _itself,
+
+    fn new_circle(radius: AnyNumber, options: Hash) -> Collider {
+        let options = options.ok();
+        let shape = LocalShape::Circle {
+            offset: offset_option(&options),
+            radius: radius.unwrap().to_f32().abs(),
+        };
+        Collider::new(shape, options)
+    }
+
+    fn new_rect(width: AnyNumber, height: AnyNumber, options: Hash) -> Collider {
+        let options = options.ok();
+        let size = Vector2::new(width.unwrap().to_f32(), height.unwrap().to_f32());
+        let shape = LocalShape::Rect {
+            offset: offset_option(&options),
+            size: size.abs(),
+        };
+        Collider::new(shape, options)
+    }
+
+    fn new_polygon(points: Array, options: Hash) -> AnyObject {
+        let points = points
+            .unwrap()
+            .into_iter()
+            .filter_map(|p| p.try_convert_to::<VectorData>().ok())
+            .map(Into::into)
+            .collect::<Vec<Vector2<f32>>>();
+        match convex_hull(&points) {
+            Some(hull) => Collider::new(LocalShape::Polygon(hull), options.ok()).to_any_object(),
+            None => NilClass::new().to_any_object(),
+        }
+    }
+
+    fn get_shape() -> Symbol {
+        Symbol::new(match _itself.get_data(&*COLLIDER_WRAPPER).shape {
+            LocalShape::Circle { .. } => "circle",
+            LocalShape::Rect { .. } => "rect",
+            LocalShape::Polygon(_) => "polygon",
+        })
+    }
+
+    fn get_layer() -> Symbol {
+        Symbol::new(&_itself.get_data(&*COLLIDER_WRAPPER).layer)
+    }
+
+    fn set_layer(layer: Symbol) -> Symbol {
+        let layer = layer.unwrap();
+        let inner = _itself.get_data_mut(&*COLLIDER_WRAPPER);
+        inner.bit = PhysicsModule::register_layer(layer.to_str());
+        inner.layer = layer.to_str().to_owned();
+        layer
+    }
+
+    fn is_trigger() -> Boolean {
+        Boolean::new(_itself.is_trigger())
+    }
+
+    fn set_trigger(trigger: Boolean) -> Boolean {
+        let trigger = trigger.map(|t| t.to_bool()).unwrap_or(false);
+        _itself.get_data_mut(&*COLLIDER_WRAPPER).trigger = trigger;
+        Boolean::new(trigger)
+    }
+);
+
+#[rustfmt::skip]
+methods!(
+    GameObject,
+    _itself,
+
+    fn set_collision_mask(mask: AnyObject) -> AnyObject {
+        let mask = mask.unwrap_or_else(|_| NilClass::new().to_any_object());
+        let inner = if let Ok(layers) = mask.try_convert_to::<Array>() {
+            let layers = layers
+                .into_iter()
+                .filter_map(|l| l.try_convert_to::<Symbol>().ok())
+                .map(|l| l.to_str().to_owned())
+                .collect();
+            Some(MaskInner { layers, bits: 0, resolved: 0 })
+        } else if let Ok(bits) = mask.try_convert_to::<Integer>() {
+            Some(MaskInner { layers: Vec::new(), bits: bits.to_i64() as u32, resolved: 0 })
+        } else {
+            None
+        };
+        let bits = match inner {
+            Some(inner) => Class::from_existing("Object").wrap_data(inner, &*MASK_WRAPPER),
+            None => NilClass::new().to_any_object(),
+        };
+        _itself.instance_variable_set("@collision_bits", bits);
+        _itself.instance_variable_set("@collision_mask", mask)
+    }
+);
+
+impl VerifiedObject for Collider {
+    fn is_correct_type<T: Object>(object: &T) -> bool {
+        object.class() == Class::from_existing("Collider")
+    }
+
+    fn error_message() -> &'static str { "Object is not of type Collider" }
+}
+
+pub fn add_collider_class() {
+    let mut class = Class::new("Collider", None);
+    class.def_self("circle", new_circle);
+    class.def_self("rect", new_rect);
+    class.def_self("polygon", new_polygon);
+
+    class.def("shape", get_shape);
+    class.def("layer", get_layer);
+    class.def("layer=", set_layer);
+    class.def("trigger?", is_trigger);
+    class.def("trigger=", set_trigger);
+
+    // Masks are resolved to layer bits when assigned rather than every step
+    Class::from_existing("GameObject").def("collision_mask=", set_collision_mask);
+}
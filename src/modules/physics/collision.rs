@@ -0,0 +1,199 @@
+use super::body::{BODY_WRAPPER, DEFAULT_FRICTION};
+use super::shape::{self, Contact, Shape};
+use super::{broadphase, stepped_objects, PHYSICS_WRAPPER};
+use crate::modules::core::{CoreModule, GameInner, GameObject, TransformStore};
+use crate::modules::{EngineModule, GameState, COLLISION_MODULE, CORE_MODULE, PHYSICS_MODULE};
+use nalgebra::Vector2;
+use rutie::{Module, Object};
+use std::collections::HashSet;
+
+/// Share of the remaining overlap that each step pushes apart
+const CORRECTION: f32 = 0.8;
+/// Overlap left alone, so that resting objects don't jitter
+const SLOP: f32 = 0.01;
+
+/// Finds overlaps between colliders in every fixed update, after the physics
+/// module has moved the bodies, separates them and calls the collision hooks.
+/// Objects with a collider but no body are treated as immovable. The module is
+/// always added, so games without physics still get their collisions.
+#[derive(Default)]
+pub struct CollisionModule {
+    /// Pairs of ids, smaller first, whose colliders overlapped last step
+    touching: HashSet<(u64, u64)>,
+}
+
+/// A collider placed in the world for one step
+struct Placed {
+    game_object: GameObject,
+    id: u64,
+    shape: Shape,
+    layer: u32,
+    mask: u32,
+    trigger: bool,
+    inverse_mass: f32,
+    restitution: f32,
+    friction: f32,
+}
+
+/// An overlap found in one step, with the contact seen from `a`
+struct Collision {
+    a: GameObject,
+    b: GameObject,
+    key: (u64, u64),
+    contact: Contact,
+}
+
+impl CollisionModule {
+    /// Collision hooks are called once the step is over, so scripts see every
+    /// object where it came to rest. Pairs that start touching get
+    /// `on_collision_enter!` before their first `on_collision!`, and pairs that
+    /// stop get `on_collision_exit!`.
+    fn step(&mut self) {
+        let (collisions, held) = CoreModule::inspect_mut(|game| {
+            let (game_objects, held) = stepped_objects(game);
+            (self.collide(game, &game_objects), held)
+        });
+        // Objects that are only paused, e.g. with their scene, keep their pairs
+        let mut touching = self
+            .touching
+            .iter()
+            .filter(|(a, b)| held.contains(a) || held.contains(b))
+            .cloned()
+            .collect::<HashSet<_>>();
+        for Collision { a, b, key, contact } in collisions {
+            if !self.touching.contains(&key) {
+                a.on_collision_enter(&b, contact);
+                b.on_collision_enter(&a, contact.flipped());
+            }
+            a.on_collision(&b, contact);
+            b.on_collision(&a, contact.flipped());
+            touching.insert(key);
+        }
+
+        let ended = self.touching.difference(&touching).cloned().collect::<Vec<_>>();
+        self.touching = touching;
+        for (a, b) in ended {
+            let (a, b) = CoreModule::inspect(|game| {
+                (game.index.get(a).cloned(), game.index.get(b).cloned())
+            });
+            if let Some(a) = &a {
+                a.on_collision_exit(b.as_ref());
+            }
+            if let Some(b) = &b {
+                b.on_collision_exit(a.as_ref());
+            }
+        }
+    }
+    /// Finds every overlap between colliders whose layers and masks let them
+    /// meet. Overlaps between solid colliders where at least one body can move
+    /// are resolved; triggers only report theirs.
+    fn collide(&self, game: &mut GameInner, game_objects: &[GameObject]) -> Vec<Collision> {
+        let physics = Module::from_existing("Physics").instance_variable_get("@inner");
+        let physics = physics.get_data(&*PHYSICS_WRAPPER);
+        let placed = game_objects
+            .iter()
+            .filter_map(|game_object| {
+                let id = game_object.id()?;
+                let collider = game_object.collider()?;
+                let world = game.hierarchy.world_transform(&game.transforms, game_object);
+                let (inverse_mass, restitution, friction) = match game_object.body() {
+                    Some(body) => {
+                        let body = body.get_data(&*BODY_WRAPPER);
+                        (body.inverse_mass(), body.restitution, body.friction)
+                    }
+                    None => (0.0, 0.0, DEFAULT_FRICTION),
+                };
+                Some(Placed {
+                    game_object: game_object.clone(),
+                    id,
+                    shape: collider.shape_at(&world),
+                    layer: collider.layer(),
+                    mask: game_object.collision_mask(physics),
+                    trigger: collider.is_trigger(),
+                    inverse_mass,
+                    restitution,
+                    friction,
+                })
+            })
+            .collect::<Vec<_>>();
+        let bounds = placed.iter().map(|p| p.shape.bounds()).collect::<Vec<_>>();
+
+        let mut collisions = Vec::new();
+        for (i, j) in broadphase::overlapping_pairs(&bounds) {
+            let (a, b) = (&placed[i], &placed[j]);
+            if a.mask & b.layer == 0 || b.mask & a.layer == 0 {
+                continue;
+            }
+            let contact = match shape::collide(&a.shape, &b.shape) {
+                Some(contact) => contact,
+                None => continue,
+            };
+            if !a.trigger && !b.trigger && a.inverse_mass + b.inverse_mass > 0.0 {
+                resolve(&mut game.transforms, a, b, &contact);
+            }
+            collisions.push(Collision {
+                a: a.game_object.clone(),
+                b: b.game_object.clone(),
+                key: (a.id.min(b.id), a.id.max(b.id)),
+                contact,
+            });
+        }
+        collisions
+    }
+}
+
+/// Exchanges an impulse along the contact normal, with friction along its
+/// tangent, and pushes the two objects apart in proportion to their inverse
+/// masses.
+fn resolve(transforms: &mut TransformStore, a: &Placed, b: &Placed, contact: &Contact) {
+    let total = a.inverse_mass + b.inverse_mass;
+    let normal = contact.normal;
+    let velocity_a = transforms.velocity(a.id).unwrap_or_else(Vector2::zeros);
+    let velocity_b = transforms.velocity(b.id).unwrap_or_else(Vector2::zeros);
+    let relative = velocity_b - velocity_a;
+    let closing = relative.dot(&normal);
+    if closing < 0.0 {
+        let restitution = a.restitution.max(b.restitution);
+        let j = -(1.0 + restitution) * closing / total;
+        let mut impulse = normal * j;
+
+        let tangent = relative - normal * closing;
+        if tangent.norm() > std::f32::EPSILON {
+            let tangent = tangent.normalize();
+            let limit = j * (a.friction * b.friction).sqrt();
+            let jt = (-relative.dot(&tangent) / total).max(-limit).min(limit);
+            impulse += tangent * jt;
+        }
+        if a.inverse_mass > 0.0 {
+            transforms.set_velocity(a.id, velocity_a - impulse * a.inverse_mass);
+        }
+        if b.inverse_mass > 0.0 {
+            transforms.set_velocity(b.id, velocity_b + impulse * b.inverse_mass);
+        }
+    }
+
+    let correction = normal * ((contact.penetration - SLOP).max(0.0) / total * CORRECTION);
+    if let Some(transform) = transforms.get_mut(a.id) {
+        transform.position -= correction * a.inverse_mass;
+    }
+    if let Some(transform) = transforms.get_mut(b.id) {
+        transform.position += correction * b.inverse_mass;
+    }
+}
+impl<G> EngineModule<G> for CollisionModule
+where
+    G: GameState,
+{
+    fn name(&self) -> &'static str { COLLISION_MODULE }
+
+    fn run_after(&self) -> Vec<&'static str> { vec![CORE_MODULE, PHYSICS_MODULE] }
+
+    fn init(&mut self, _: &mut G) {
+        super::add_physics_module();
+        super::body::add_body_class();
+        super::collider::add_collider_class();
+        super::contact::add_contact_class();
+    }
+
+    fn fixed_update(&mut self, _: &mut G) { self.step(); }
+}
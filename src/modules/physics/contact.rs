@@ -0,0 +1,74 @@
+use super::shape::Contact;
+use crate::modules::core::{GameObject, ScriptError, ScriptPhase, VectorData};
+use rutie::{AnyObject, Class, Float, NilClass, Object};
+
+wrappable_struct!(Contact, ContactWrapper, CONTACT_WRAPPER);
+
+/// The Ruby-facing view of a contact, seen from the object it is given to
+class!(ContactData);
+
+impl From<Contact> for ContactData {
+    fn from(contact: Contact) -> Self {
+        Class::from_existing("Contact").wrap_data(contact, &*CONTACT_WRAPPER)
+    }
+}
+
+impl GameObject {
+    /// Calls `on_collision_enter!` with the contact, whose normal points from
+    /// this object towards `other`
+    pub fn on_collision_enter(&self, other: &GameObject, contact: Contact) {
+        let contact = ContactData::from(contact);
+        self.send_collision_hook(
+            "on_collision_enter!",
+            &[other.to_any_object(), contact.to_any_object()],
+        );
+    }
+
+    /// Calls `on_collision!` with the contact, whose normal points from this
+    /// object towards `other`
+    pub fn on_collision(&self, other: &GameObject, contact: Contact) {
+        let contact = ContactData::from(contact);
+        self.send_collision_hook(
+            "on_collision!",
+            &[other.to_any_object(), contact.to_any_object()],
+        );
+    }
+
+    /// Calls `on_collision_exit!` with the other object, or `nil` if it has
+    /// been deleted
+    pub fn on_collision_exit(&self, other: Option<&GameObject>) {
+        let other = other.map_or_else(|| NilClass::new().to_any_object(), |o| o.to_any_object());
+        self.send_collision_hook("on_collision_exit!", &[other]);
+    }
+
+    fn send_collision_hook(&self, hook: &str, arguments: &[AnyObject]) {
+        if let Err(e) = self.protect_send(hook, arguments) {
+            ScriptError::new(&e, ScriptPhase::Collision).with_object(self).report();
+        }
+    }
+}
+
+#[rustfmt::skip]
+methods!(
+    ContactData,
+    _itself,
+
+    fn get_normal() -> VectorData {
+        _itself.get_data(&*CONTACT_WRAPPER).normal.into()
+    }
+
+    fn get_penetration() -> Float {
+        Float::new(_itself.get_data(&*CONTACT_WRAPPER).penetration as f64)
+    }
+
+    fn get_point() -> VectorData {
+        _itself.get_data(&*CONTACT_WRAPPER).point.into()
+    }
+);
+
+pub fn add_contact_class() {
+    let mut class = Class::new("Contact", None);
+    class.def("normal", get_normal);
+    class.def("penetration", get_penetration);
+    class.def("point", get_point);
+}
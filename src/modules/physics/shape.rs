@@ -0,0 +1,493 @@
+use nalgebra::Vector2;
+use std::cmp::Ordering;
+
+/// A collider placed in the world. Rects that aren't rotated stay axis
+/// aligned; rotated rects become polygons.
+#[derive(Clone, Debug)]
+pub enum Shape {
+    Circle { center: Vector2<f32>, radius: f32 },
+    Aabb(Aabb),
+    /// Convex, with its points in counter-clockwise order
+    Polygon(Vec<Vector2<f32>>),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+}
+
+/// Where two shapes overlap. The normal points from the first shape towards
+/// the second, and moving the second shape `penetration` along it separates
+/// them.
+#[derive(Clone, Copy, Debug)]
+pub struct Contact {
+    pub normal: Vector2<f32>,
+    pub penetration: f32,
+    pub point: Vector2<f32>,
+}
+
+impl Aabb {
+    pub fn center(&self) -> Vector2<f32> { (self.min + self.max) / 2.0 }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    fn corners(&self) -> Vec<Vector2<f32>> {
+        vec![
+            self.min,
+            Vector2::new(self.max.x, self.min.y),
+            self.max,
+            Vector2::new(self.min.x, self.max.y),
+        ]
+    }
+}
+
+impl Contact {
+    /// The same contact seen from the other shape
+    pub fn flipped(self) -> Contact {
+        Contact {
+            normal: -self.normal,
+            ..self
+        }
+    }
+}
+
+impl Shape {
+    pub fn bounds(&self) -> Aabb {
+        match self {
+            Shape::Circle { center, radius } => Aabb {
+                min: center - Vector2::new(*radius, *radius),
+                max: center + Vector2::new(*radius, *radius),
+            },
+            Shape::Aabb(aabb) => *aabb,
+            Shape::Polygon(points) => points.iter().skip(1).fold(
+                Aabb {
+                    min: points[0],
+                    max: points[0],
+                },
+                |aabb, p| Aabb {
+                    min: aabb.min.inf(p),
+                    max: aabb.max.sup(p),
+                },
+            ),
+        }
+    }
+
+    fn points(&self) -> Vec<Vector2<f32>> {
+        match self {
+            Shape::Circle { center, .. } => vec![*center],
+            Shape::Aabb(aabb) => aabb.corners(),
+            Shape::Polygon(points) => points.clone(),
+        }
+    }
+}
+
+/// Tests two shapes for overlap, returning the contact if they touch
+pub fn collide(a: &Shape, b: &Shape) -> Option<Contact> {
+    match (a, b) {
+        (
+            Shape::Circle {
+                center: a,
+                radius: ra,
+            },
+            Shape::Circle {
+                center: b,
+                radius: rb,
+            },
+        ) => circles(a, *ra, b, *rb),
+        (Shape::Aabb(a), Shape::Aabb(b)) => aabbs(a, b),
+        (Shape::Circle { center, radius }, b) => {
+            polygon_circle(&b.points(), center, *radius).map(Contact::flipped)
+        }
+        (a, Shape::Circle { center, radius }) => polygon_circle(&a.points(), center, *radius),
+        (a, b) => polygons(&a.points(), &b.points()),
+    }
+}
+
+/// The convex hull of the points in counter-clockwise order, or `None` when
+/// they don't enclose any area. Points that aren't finite are left out.
+pub fn convex_hull(points: &[Vector2<f32>]) -> Option<Vec<Vector2<f32>>> {
+    let mut sorted = points
+        .iter()
+        .filter(|p| p.x.is_finite() && p.y.is_finite())
+        .copied()
+        .collect::<Vec<_>>();
+    sorted.sort_by(|a, b| {
+        (a.x, a.y)
+            .partial_cmp(&(b.x, b.y))
+            .unwrap_or(Ordering::Equal)
+    });
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return None;
+    }
+    let mut hull = half_hull(sorted.iter());
+    hull.extend(half_hull(sorted.iter().rev()));
+    if hull.len() < 3 {
+        None
+    } else {
+        Some(hull)
+    }
+}
+
+/// One side of the hull, leaving out its last point, which starts the other
+fn half_hull<'a, I>(points: I) -> Vec<Vector2<f32>>
+where
+    I: Iterator<Item = &'a Vector2<f32>>,
+{
+    let mut chain: Vec<Vector2<f32>> = Vec::new();
+    for p in points {
+        while chain.len() >= 2 {
+            let (a, b) = (chain[chain.len() - 2], chain[chain.len() - 1]);
+            if (b - a).perp(&(p - a)) > 0.0 {
+                break;
+            }
+            chain.pop();
+        }
+        chain.push(*p);
+    }
+    chain.pop();
+    chain
+}
+
+fn circles(a: &Vector2<f32>, ra: f32, b: &Vector2<f32>, rb: f32) -> Option<Contact> {
+    let offset = b - a;
+    let distance = offset.norm();
+    if distance >= ra + rb {
+        return None;
+    }
+    let normal = if distance > 0.0 {
+        offset / distance
+    } else {
+        Vector2::new(1.0, 0.0)
+    };
+    Some(Contact {
+        normal,
+        penetration: ra + rb - distance,
+        point: a + normal * ra,
+    })
+}
+
+fn aabbs(a: &Aabb, b: &Aabb) -> Option<Contact> {
+    let overlap_x = a.max.x.min(b.max.x) - a.min.x.max(b.min.x);
+    let overlap_y = a.max.y.min(b.max.y) - a.min.y.max(b.min.y);
+    if overlap_x <= 0.0 || overlap_y <= 0.0 {
+        return None;
+    }
+    let offset = b.center() - a.center();
+    let (normal, penetration) = if overlap_x < overlap_y {
+        (Vector2::new(offset.x.signum(), 0.0), overlap_x)
+    } else {
+        (Vector2::new(0.0, offset.y.signum()), overlap_y)
+    };
+    let min = a.min.sup(&b.min);
+    let max = a.max.inf(&b.max);
+    Some(Contact {
+        normal,
+        penetration,
+        point: (min + max) / 2.0,
+    })
+}
+
+/// Outward normals of a counter-clockwise polygon's edges. Edges without
+/// length, as in shapes scaled down to nothing, have no normal and are skipped.
+fn edge_normals(points: &[Vector2<f32>]) -> impl Iterator<Item = (usize, Vector2<f32>)> + '_ {
+    (0..points.len()).filter_map(move |i| {
+        let edge = points[(i + 1) % points.len()] - points[i];
+        if edge.norm() > std::f32::EPSILON {
+            Some((i, Vector2::new(edge.y, -edge.x).normalize()))
+        } else {
+            None
+        }
+    })
+}
+
+fn polygon_circle(
+    points: &[Vector2<f32>],
+    center: &Vector2<f32>,
+    radius: f32,
+) -> Option<Contact> {
+    let (normal, separation) = edge_normals(points)
+        .map(|(i, normal)| (normal, normal.dot(&(center - points[i]))))
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))?;
+    if separation > radius {
+        return None;
+    }
+    if separation <= 0.0 {
+        // The center is inside, so push out through the nearest edge
+        return Some(Contact {
+            normal,
+            penetration: radius - separation,
+            point: center - normal * separation,
+        });
+    }
+    let closest = (0..points.len())
+        .map(|i| closest_on_segment(center, &points[i], &points[(i + 1) % points.len()]))
+        .min_by(|a, b| {
+            (center - a)
+                .norm_squared()
+                .partial_cmp(&(center - b).norm_squared())
+                .unwrap_or(Ordering::Equal)
+        })?;
+    let offset = center - closest;
+    let distance = offset.norm();
+    if distance >= radius {
+        return None;
+    }
+    Some(Contact {
+        normal: if distance > 0.0 { offset / distance } else { normal },
+        penetration: radius - distance,
+        point: closest,
+    })
+}
+
+/// Separating axis test over both polygons' edge normals
+fn polygons(a: &[Vector2<f32>], b: &[Vector2<f32>]) -> Option<Contact> {
+    let project = |points: &[Vector2<f32>], axis: &Vector2<f32>| {
+        points
+            .iter()
+            .map(|p| p.dot(axis))
+            .fold((std::f32::INFINITY, std::f32::NEG_INFINITY), |(min, max), d| {
+                (min.min(d), max.max(d))
+            })
+    };
+    let mut best: Option<(Vector2<f32>, f32)> = None;
+    for (_, axis) in edge_normals(a).chain(edge_normals(b)) {
+        let (min_a, max_a) = project(a, &axis);
+        let (min_b, max_b) = project(b, &axis);
+        let overlap = max_a.min(max_b) - min_a.max(min_b);
+        if overlap <= 0.0 {
+            return None;
+        }
+        if best.map_or(true, |(_, least)| overlap < least) {
+            best = Some((axis, overlap));
+        }
+    }
+    let (mut normal, penetration) = best?;
+    if normal.dot(&(centroid(b) - centroid(a))) < 0.0 {
+        normal = -normal;
+    }
+    // The point of `b` furthest into `a`
+    let point = b
+        .iter()
+        .min_by(|p, q| {
+            p.dot(&normal)
+                .partial_cmp(&q.dot(&normal))
+                .unwrap_or(Ordering::Equal)
+        })
+        .copied()?;
+    Some(Contact {
+        normal,
+        penetration,
+        point,
+    })
+}
+
+fn closest_on_segment(p: &Vector2<f32>, a: &Vector2<f32>, b: &Vector2<f32>) -> Vector2<f32> {
+    let edge = b - a;
+    let length = edge.norm_squared();
+    if length <= 0.0 {
+        return *a;
+    }
+    let t = ((p - a).dot(&edge) / length).max(0.0).min(1.0);
+    a + edge * t
+}
+
+fn centroid(points: &[Vector2<f32>]) -> Vector2<f32> {
+    points.iter().fold(Vector2::zeros(), |sum, p| sum + p) / points.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn square(min: Vector2<f32>, size: f32) -> Vec<Vector2<f32>> {
+        vec![
+            min,
+            min + Vector2::new(size, 0.0),
+            min + Vector2::new(size, size),
+            min + Vector2::new(0.0, size),
+        ]
+    }
+
+    fn circle(x: f32, y: f32, radius: f32) -> Shape {
+        Shape::Circle {
+            center: Vector2::new(x, y),
+            radius,
+        }
+    }
+
+    fn assert_contact(contact: Option<Contact>, normal: Vector2<f32>, penetration: f32) {
+        let contact = contact.expect("shapes should overlap");
+        assert!(
+            (contact.normal - normal).norm() < EPSILON,
+            "normal {:?}, expected {:?}",
+            contact.normal,
+            normal
+        );
+        assert!(
+            (contact.penetration - penetration).abs() < EPSILON,
+            "penetration {}, expected {}",
+            contact.penetration,
+            penetration
+        );
+    }
+
+    #[test]
+    fn circles_overlap_along_their_centers() {
+        let contact = collide(&circle(0.0, 0.0, 1.0), &circle(1.5, 0.0, 1.0));
+        assert_contact(contact, Vector2::new(1.0, 0.0), 0.5);
+        assert!((contact.unwrap().point - Vector2::new(1.0, 0.0)).norm() < EPSILON);
+    }
+
+    #[test]
+    fn circles_apart_or_touching_dont_collide() {
+        assert!(collide(&circle(0.0, 0.0, 1.0), &circle(3.0, 0.0, 1.0)).is_none());
+        assert!(collide(&circle(0.0, 0.0, 1.0), &circle(2.0, 0.0, 1.0)).is_none());
+    }
+
+    #[test]
+    fn concentric_circles_still_get_a_normal() {
+        let contact = collide(&circle(1.0, 1.0, 1.0), &circle(1.0, 1.0, 2.0));
+        assert_contact(contact, Vector2::new(1.0, 0.0), 3.0);
+    }
+
+    #[test]
+    fn aabbs_separate_along_the_smaller_overlap() {
+        let a = Shape::Aabb(Aabb {
+            min: Vector2::new(0.0, 0.0),
+            max: Vector2::new(2.0, 2.0),
+        });
+        let b = Shape::Aabb(Aabb {
+            min: Vector2::new(1.5, 0.5),
+            max: Vector2::new(3.5, 2.5),
+        });
+        assert_contact(collide(&a, &b), Vector2::new(1.0, 0.0), 0.5);
+        assert_contact(collide(&b, &a), Vector2::new(-1.0, 0.0), 0.5);
+
+        let below = Shape::Aabb(Aabb {
+            min: Vector2::new(0.5, -1.75),
+            max: Vector2::new(1.5, 0.25),
+        });
+        assert_contact(collide(&a, &below), Vector2::new(0.0, -1.0), 0.25);
+    }
+
+    #[test]
+    fn aabbs_apart_dont_collide() {
+        let a = Shape::Aabb(Aabb {
+            min: Vector2::new(0.0, 0.0),
+            max: Vector2::new(1.0, 1.0),
+        });
+        let b = Shape::Aabb(Aabb {
+            min: Vector2::new(0.5, 2.0),
+            max: Vector2::new(1.5, 3.0),
+        });
+        assert!(collide(&a, &b).is_none());
+    }
+
+    #[test]
+    fn polygon_and_circle_outside_an_edge() {
+        let polygon = Shape::Polygon(square(Vector2::zeros(), 2.0));
+        let contact = collide(&polygon, &circle(2.5, 1.0, 1.0));
+        assert_contact(contact, Vector2::new(1.0, 0.0), 0.5);
+        assert!((contact.unwrap().point - Vector2::new(2.0, 1.0)).norm() < EPSILON);
+
+        // The normal is flipped when the circle comes first
+        let contact = collide(&circle(2.5, 1.0, 1.0), &polygon);
+        assert_contact(contact, Vector2::new(-1.0, 0.0), 0.5);
+    }
+
+    #[test]
+    fn polygon_and_circle_near_a_corner() {
+        let polygon = Shape::Polygon(square(Vector2::zeros(), 2.0));
+        let contact = collide(&polygon, &circle(2.3, 2.4, 1.0));
+        assert_contact(contact, Vector2::new(0.6, 0.8), 0.5);
+        assert!(collide(&polygon, &circle(2.8, 2.8, 1.0)).is_none());
+    }
+
+    #[test]
+    fn circle_center_inside_polygon_is_pushed_out_the_nearest_edge() {
+        let polygon = Shape::Polygon(square(Vector2::zeros(), 2.0));
+        let contact = collide(&polygon, &circle(1.8, 1.0, 0.5));
+        assert_contact(contact, Vector2::new(1.0, 0.0), 0.7);
+    }
+
+    #[test]
+    fn polygons_separate_along_the_least_overlap() {
+        let a = Shape::Polygon(square(Vector2::zeros(), 2.0));
+        let b = Shape::Polygon(square(Vector2::new(1.5, 0.25), 2.0));
+        assert_contact(collide(&a, &b), Vector2::new(1.0, 0.0), 0.5);
+        assert_contact(collide(&b, &a), Vector2::new(-1.0, 0.0), 0.5);
+    }
+
+    #[test]
+    fn rotated_polygon_against_an_aabb() {
+        let diamond = Shape::Polygon(vec![
+            Vector2::new(0.0, -1.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(0.0, 1.0),
+            Vector2::new(-1.0, 0.0),
+        ]);
+        let floor = Shape::Aabb(Aabb {
+            min: Vector2::new(-5.0, -3.0),
+            max: Vector2::new(5.0, -0.75),
+        });
+        assert_contact(collide(&diamond, &floor), Vector2::new(0.0, -1.0), 0.25);
+    }
+
+    #[test]
+    fn polygons_apart_dont_collide() {
+        let a = Shape::Polygon(square(Vector2::zeros(), 1.0));
+        let b = Shape::Polygon(square(Vector2::new(1.5, 0.0), 1.0));
+        assert!(collide(&a, &b).is_none());
+    }
+
+    #[test]
+    fn hull_keeps_only_the_outer_points_counter_clockwise() {
+        let mut points = square(Vector2::zeros(), 2.0);
+        points.push(Vector2::new(1.0, 1.0));
+        points.reverse();
+        let hull = convex_hull(&points).unwrap();
+        assert_eq!(hull, square(Vector2::zeros(), 2.0));
+    }
+
+    #[test]
+    fn degenerate_hulls_are_rejected() {
+        assert!(convex_hull(&[]).is_none());
+        assert!(convex_hull(&[Vector2::new(1.0, 1.0); 5]).is_none());
+        let collinear = (0..5).map(|i| Vector2::new(i as f32, i as f32 * 2.0)).collect::<Vec<_>>();
+        assert!(convex_hull(&collinear).is_none());
+        let not_finite = vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(std::f32::NAN, 1.0),
+            Vector2::new(0.0, std::f32::INFINITY),
+        ];
+        assert!(convex_hull(&not_finite).is_none());
+    }
+
+    #[test]
+    fn zero_length_edges_are_skipped() {
+        let mut points = square(Vector2::zeros(), 2.0);
+        points.insert(2, points[1]);
+        assert_eq!(edge_normals(&points).count(), 4);
+        let polygon = Shape::Polygon(points);
+        assert_contact(collide(&polygon, &circle(2.5, 1.0, 1.0)), Vector2::new(1.0, 0.0), 0.5);
+        let other = Shape::Polygon(square(Vector2::new(1.5, 0.0), 2.0));
+        assert_contact(collide(&polygon, &other), Vector2::new(1.0, 0.0), 0.5);
+    }
+
+    #[test]
+    fn polygons_scaled_to_a_point_never_collide() {
+        let point = Shape::Polygon(vec![Vector2::new(1.0, 1.0); 4]);
+        assert!(collide(&point, &circle(1.0, 1.0, 1.0)).is_none());
+        assert!(collide(&point, &Shape::Polygon(square(Vector2::zeros(), 2.0))).is_none());
+    }
+}